@@ -0,0 +1,571 @@
+//! Connections whose network waits are driven by tokio's reactor.
+//!
+//! The futures call the client library's nonblocking API and register the
+//! connection's socket with tokio whenever a call would block. No threads
+//! are blocked, but the futures must be polled within a tokio runtime.
+//!
+//! Either MySQL's `*_nonblocking` functions (`mysql_nonblocking`) or
+//! MariaDB's `_start`/`_cont` functions (`mariadb_nonblocking`) are used,
+//! behind the same API. MySQL's are used if both features are enabled.
+//!
+//! Result sets are read whole with `store_result`. Rows cannot be streamed
+//! as with `Connection::use_result`, since fetching each row may wait on the
+//! network and no nonblocking row fetch is driven here.
+//!
+//! Dropping one of these futures before it completes leaves the connection
+//! in the middle of a call. The connection is then poisoned: later calls,
+//! `get_mut` and `into_inner` fail with `Error::Poisoned`, and it can only
+//! be dropped.
+
+use std::future::{Future, poll_fn};
+use std::io;
+use std::mem::{self, ManuallyDrop};
+use std::os::raw::{c_char, c_ulong};
+use std::os::unix::io::RawFd;
+use std::pin::Pin;
+use std::ptr;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::io::unix::AsyncFd;
+use tokio::time::{Sleep, sleep};
+
+use connection::{ConnectArgs, ConnectOptions, Connection};
+use error::{Error, Result};
+use handle_info;
+use result::BufferedResult;
+
+/// The events a nonblocking call is waiting for.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Wait {
+    pub(crate) read: bool,
+    pub(crate) write: bool,
+    pub(crate) timeout: Option<Duration>,
+}
+
+/// The events that occurred since a call last returned `Step::Wait`. All
+/// are unset for the first attempt.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Fired {
+    pub(crate) read: bool,
+    pub(crate) write: bool,
+    pub(crate) timeout: bool,
+}
+
+/// The outcome of one attempt of a nonblocking call.
+pub(crate) enum Step<T> {
+    Ready(T),
+    Wait(Wait),
+}
+
+/// A connection whose queries are sent and received without blocking.
+pub struct AsyncConnection {
+    fd: Option<AsyncFd<RawFd>>,
+    waiting: Option<Wait>,
+    timer: Option<Pin<Box<Sleep>>>,
+    poisoned: bool,
+    // Closed by `Drop`, after the socket has been deregistered.
+    conn: ManuallyDrop<Connection>,
+    // The text of the last query, which the client library may still point
+    // to while the call is pending. Only dropped after `conn` is closed.
+    sql: String,
+}
+
+impl Drop for AsyncConnection {
+    fn drop(&mut self) {
+        self.fd = None;
+        unsafe { backend::close(&mut self.conn, self.poisoned) }
+    }
+}
+
+/// Marks the connection poisoned if the call it guards is dropped after it
+/// started but before it completed.
+struct Call<'a> {
+    conn: &'a mut AsyncConnection,
+    started: bool,
+    done: bool,
+}
+
+impl<'a> Drop for Call<'a> {
+    fn drop(&mut self) {
+        if self.started && !self.done {
+            self.conn.poisoned = true;
+        }
+    }
+}
+
+impl AsyncConnection {
+    /// Opens a connection with the nonblocking variant of
+    /// `mysql_real_connect`.
+    pub fn connect(opts: &ConnectOptions) -> impl Future<Output = Result<AsyncConnection>> {
+        let prepared = ConnectArgs::new(opts).and_then(|args| {
+            let mut conn = Connection::init()?;
+            conn.opts = Some(opts.clone());
+            let conn = AsyncConnection {
+                fd: None,
+                waiting: None,
+                timer: None,
+                poisoned: false,
+                conn: ManuallyDrop::new(conn),
+                sql: String::new(),
+            };
+            Ok((conn, args))
+        });
+        let mut state = Some(prepared.map(|(conn, args)| (conn, backend::connect(args))));
+        poll_fn(move |cx| {
+            let (mut this, mut step) = match state.take().expect("future polled after completion") {
+                Ok(state) => state,
+                Err(e) => return Poll::Ready(Err(e)),
+            };
+            this.poisoned = false;
+            match this.poll_step(cx, &mut step) {
+                Poll::Ready(result) => {
+                    let result = result.and_then(|_| unsafe {
+                        handle_info::check_layout(this.conn.as_ptr())
+                    });
+                    this.conn.refresh_thread_id();
+                    Poll::Ready(result.map(|_| this))
+                }
+                Poll::Pending => {
+                    // Dropping the future now drops the connection in the
+                    // middle of connecting.
+                    this.poisoned = true;
+                    state = Some(Ok((this, step)));
+                    Poll::Pending
+                }
+            }
+        })
+    }
+
+    /// Sends `sql` and reads the server's reply. A result set must then be
+    /// read with `store_result`.
+    pub fn query<'a>(&'a mut self, sql: &str) -> impl Future<Output = Result<()>> + 'a {
+        let sql = sql.to_owned();
+        self.call(move |this| {
+            this.sql = sql;
+            // The text stays in `this.sql` until the next call starts, which
+            // is only once this one has completed.
+            backend::query(this.sql.as_ptr() as *const c_char, this.sql.len() as c_ulong)
+        })
+    }
+
+    /// Reads the entire result set of the last query, or `None` if it did
+    /// not produce one.
+    pub fn store_result(&mut self)
+                        -> impl Future<Output = Result<Option<BufferedResult>>> + '_ {
+        self.call(|_| backend::store_result())
+    }
+
+    /// Advances to the next result of a multi-statement query, returning
+    /// `false` if there are no more results.
+    pub fn next_result(&mut self) -> impl Future<Output = Result<bool>> + '_ {
+        self.call(|_| backend::next_result())
+    }
+
+    pub fn get_ref(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// The underlying connection, for calls that do not wait on the network
+    /// such as `affected_rows`. Blocking calls made through it block the
+    /// executor thread. Fails if the connection is poisoned.
+    pub fn get_mut(&mut self) -> Result<&mut Connection> {
+        if self.poisoned {
+            return Err(Error::Poisoned);
+        }
+        Ok(&mut self.conn)
+    }
+
+    /// Returns the underlying connection, or fails if it is poisoned.
+    pub fn into_inner(self) -> Result<Connection> {
+        if self.poisoned {
+            return Err(Error::Poisoned);
+        }
+        let mut this = ManuallyDrop::new(self);
+        this.fd = None;
+        this.timer = None;
+        drop(mem::take(&mut this.sql));
+        Ok(unsafe { ptr::read(&*this.conn) })
+    }
+
+    /// Runs the step built by `start` to completion. `start` is only called
+    /// once the connection is known not to be poisoned, after the state of
+    /// any earlier call has been reset.
+    fn call<'a, T, S, F>(&'a mut self, start: S) -> impl Future<Output = Result<T>> + 'a
+        where S: FnOnce(&mut AsyncConnection) -> F + 'a,
+              F: FnMut(&mut Connection, Fired) -> Step<Result<T>> + 'a,
+              T: 'a
+    {
+        let mut start = Some(start);
+        let mut step = None;
+        let mut call = Call {
+            conn: self,
+            started: false,
+            done: false,
+        };
+        poll_fn(move |cx| {
+            if !call.started {
+                if call.conn.poisoned {
+                    call.done = true;
+                    return Poll::Ready(Err(Error::Poisoned));
+                }
+                call.conn.finish();
+                let start = start.take().expect("future polled after completion");
+                step = Some(start(call.conn));
+                call.started = true;
+            }
+            let step = step.as_mut().expect("step built when the call started");
+            let result = call.conn.poll_step(cx, step);
+            if result.is_ready() {
+                call.done = true;
+            }
+            result
+        })
+    }
+
+    /// Retries `step` until it completes, waiting for the events it asks
+    /// for in between.
+    fn poll_step<T, F>(&mut self, cx: &mut Context, step: &mut F) -> Poll<Result<T>>
+        where F: FnMut(&mut Connection, Fired) -> Step<Result<T>>
+    {
+        loop {
+            let fired = match self.waiting {
+                None => Fired::default(),
+                Some(wait) => {
+                    match self.poll_wait(cx, wait) {
+                        Poll::Ready(Ok(fired)) => fired,
+                        Poll::Ready(Err(e)) => {
+                            // The call is still pending in the client library.
+                            self.poisoned = true;
+                            return Poll::Ready(Err(e.into()));
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+            };
+            let wait = match step(&mut self.conn, fired) {
+                Step::Ready(result) => {
+                    self.finish();
+                    return Poll::Ready(result);
+                }
+                Step::Wait(wait) => wait,
+            };
+            self.timer = wait.timeout.map(|timeout| Box::pin(sleep(timeout)));
+            self.waiting = Some(wait);
+            if let Err(e) = self.register() {
+                self.poisoned = true;
+                return Poll::Ready(Err(e.into()));
+            }
+        }
+    }
+
+    fn register(&mut self) -> io::Result<()> {
+        if self.fd.is_none() {
+            // The socket stays open until `mysql_close`, which only runs
+            // after `fd` has been dropped.
+            let socket = self.conn
+                .socket_fd()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))?;
+            self.fd = Some(unsafe { AsyncFd::register(socket) }?);
+        }
+        Ok(())
+    }
+
+    /// Waits for any of the events in `wait`, clearing the readiness that is
+    /// consumed by retrying the call.
+    fn poll_wait(&mut self, cx: &mut Context, wait: Wait) -> Poll<io::Result<Fired>> {
+        let fd = self.fd.as_ref().expect("socket registered before waiting");
+        let mut fired = Fired::default();
+        if wait.read {
+            if let Poll::Ready(guard) = fd.poll_read_ready(cx) {
+                guard?.clear_ready();
+                fired.read = true;
+            }
+        }
+        if wait.write {
+            if let Poll::Ready(guard) = fd.poll_write_ready(cx) {
+                guard?.clear_ready();
+                fired.write = true;
+            }
+        }
+        if let Some(ref mut timer) = self.timer {
+            fired.timeout = timer.as_mut().poll(cx).is_ready();
+        }
+        if fired.read || fired.write || fired.timeout {
+            Poll::Ready(Ok(fired))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn finish(&mut self) {
+        self.waiting = None;
+        self.timer = None;
+    }
+}
+
+/// Steps for the MySQL 8.0 `*_nonblocking` functions, which are called
+/// again with the same arguments until they complete.
+#[cfg(feature = "mysql_nonblocking")]
+mod backend {
+    use std::mem::ManuallyDrop;
+    use std::os::raw::{c_char, c_ulong};
+    use std::ptr;
+
+    use connection::{ConnectArgs, Connection, as_ptr};
+    use error::Result;
+    use nonblocking::*;
+    use nonblocking::net_async_status::*;
+    use result::BufferedResult;
+    use super::{Fired, Step, Wait};
+
+    /// The API does not say which direction a call is blocked on.
+    const EITHER: Wait = Wait {
+        read: true,
+        write: true,
+        timeout: None,
+    };
+
+    /// Closes `conn`. `mysql_close` may be called in the middle of a
+    /// nonblocking call.
+    pub(super) unsafe fn close(conn: &mut ManuallyDrop<Connection>, _pending: bool) {
+        ManuallyDrop::drop(conn)
+    }
+
+    fn step(conn: &Connection, status: net_async_status) -> Step<Result<()>> {
+        match status {
+            NET_ASYNC_NOT_READY => Step::Wait(EITHER),
+            NET_ASYNC_ERROR => Step::Ready(Err(conn.last_error())),
+            NET_ASYNC_COMPLETE | NET_ASYNC_COMPLETE_NO_MORE_RESULTS => Step::Ready(Ok(())),
+        }
+    }
+
+    pub(super) fn connect(args: ConnectArgs)
+                          -> impl FnMut(&mut Connection, Fired) -> Step<Result<()>> {
+        move |conn, _| {
+            let status = unsafe {
+                mysql_real_connect_nonblocking(conn.as_ptr(),
+                                               as_ptr(&args.host),
+                                               as_ptr(&args.user),
+                                               as_ptr(&args.password),
+                                               as_ptr(&args.database),
+                                               args.port,
+                                               as_ptr(&args.unix_socket),
+                                               args.client_flags)
+            };
+            step(conn, status)
+        }
+    }
+
+    /// `sql` must stay valid until the call completes.
+    pub(super) fn query(sql: *const c_char, len: c_ulong)
+                        -> impl FnMut(&mut Connection, Fired) -> Step<Result<()>> {
+        move |conn, _| {
+            let status = unsafe { mysql_real_query_nonblocking(conn.as_ptr(), sql, len) };
+            step(conn, status)
+        }
+    }
+
+    type StoreResult = Result<Option<BufferedResult>>;
+
+    pub(super) fn store_result()
+                               -> impl FnMut(&mut Connection, Fired) -> Step<StoreResult> {
+        |conn, _| {
+            let mut res = ptr::null_mut();
+            let status = unsafe { mysql_store_result_nonblocking(conn.as_ptr(), &mut res) };
+            match step(conn, status) {
+                Step::Ready(Ok(())) if res.is_null() => {
+                    Step::Ready(conn.check_no_result().map(|_| None))
+                }
+                Step::Ready(result) => {
+                    Step::Ready(result.map(|_| Some(unsafe { BufferedResult::from_raw(res) })))
+                }
+                Step::Wait(wait) => Step::Wait(wait),
+            }
+        }
+    }
+
+    pub(super) fn next_result()
+                              -> impl FnMut(&mut Connection, Fired) -> Step<Result<bool>> {
+        |conn, _| {
+            let status = unsafe { mysql_next_result_nonblocking(conn.as_ptr()) };
+            match step(conn, status) {
+                Step::Ready(result) => {
+                    Step::Ready(result.map(|_| status == NET_ASYNC_COMPLETE))
+                }
+                Step::Wait(wait) => Step::Wait(wait),
+            }
+        }
+    }
+}
+
+/// Steps for MariaDB's `_start`/`_cont` functions. The first attempt calls
+/// `_start`; later ones call `_cont` with the events that occurred.
+#[cfg(all(feature = "mariadb_nonblocking", not(feature = "mysql_nonblocking")))]
+mod backend {
+    use std::mem::ManuallyDrop;
+    use std::net::Shutdown;
+    use std::os::raw::{c_char, c_int, c_ulong};
+    use std::os::unix::io::{FromRawFd, IntoRawFd};
+    use std::os::unix::net::UnixStream;
+    use std::ptr;
+    use std::time::Duration;
+
+    use connection::{ConnectArgs, Connection, as_ptr};
+    use error::Result;
+    use mariadb_nonblocking::*;
+    use result::BufferedResult;
+    use super::{Fired, Step, Wait};
+
+    /// Closes `conn`, unless a call is `pending`: its coroutine is then still
+    /// suspended inside the client library, and `mysql_close` would resume
+    /// network I/O on it. The handle is leaked instead, and the socket shut
+    /// down so the server sees the client disconnect.
+    pub(super) unsafe fn close(conn: &mut ManuallyDrop<Connection>, pending: bool) {
+        if !pending {
+            return ManuallyDrop::drop(conn);
+        }
+        if let Some(fd) = conn.socket_fd() {
+            // Only borrows the descriptor: `shutdown` applies to any socket,
+            // and `into_raw_fd` keeps it from being closed here.
+            let socket = UnixStream::from_raw_fd(fd);
+            let _ = socket.shutdown(Shutdown::Both);
+            let _ = socket.into_raw_fd();
+        }
+    }
+
+    fn wait(conn: &Connection, status: c_int) -> Wait {
+        let timeout = if status & MYSQL_WAIT_TIMEOUT != 0 {
+            let ms = unsafe { mysql_get_timeout_value_ms(conn.as_ptr()) };
+            Some(Duration::from_millis(ms.into()))
+        } else {
+            None
+        };
+        Wait {
+            read: status & (MYSQL_WAIT_READ | MYSQL_WAIT_EXCEPT) != 0,
+            write: status & MYSQL_WAIT_WRITE != 0,
+            timeout,
+        }
+    }
+
+    fn status(fired: Fired) -> c_int {
+        let mut status = 0;
+        if fired.read {
+            status |= MYSQL_WAIT_READ;
+        }
+        if fired.write {
+            status |= MYSQL_WAIT_WRITE;
+        }
+        if fired.timeout {
+            status |= MYSQL_WAIT_TIMEOUT;
+        }
+        status
+    }
+
+    /// Calls `start` on the first attempt and `cont` on later ones, returning
+    /// the events to wait for if the call has not completed.
+    fn attempt<S, C>(conn: &Connection, fired: Fired, started: &mut bool, start: S, cont: C)
+                     -> Option<Wait>
+        where S: FnOnce() -> c_int,
+              C: FnOnce(c_int) -> c_int
+    {
+        let pending = if *started {
+            cont(status(fired))
+        } else {
+            *started = true;
+            start()
+        };
+        if pending == 0 {
+            None
+        } else {
+            Some(wait(conn, pending))
+        }
+    }
+
+    pub(super) fn connect(args: ConnectArgs)
+                          -> impl FnMut(&mut Connection, Fired) -> Step<Result<()>> {
+        let mut started = false;
+        let mut ret = ptr::null_mut();
+        move |conn, fired| {
+            let mysql = conn.as_ptr();
+            let out: *mut _ = &mut ret;
+            let start = || unsafe {
+                mysql_options_raw(mysql, MYSQL_OPT_NONBLOCK, ptr::null());
+                mysql_real_connect_start(out,
+                                         mysql,
+                                         as_ptr(&args.host),
+                                         as_ptr(&args.user),
+                                         as_ptr(&args.password),
+                                         as_ptr(&args.database),
+                                         args.port,
+                                         as_ptr(&args.unix_socket),
+                                         args.client_flags)
+            };
+            let cont = |status| unsafe { mysql_real_connect_cont(out, mysql, status) };
+            if let Some(wait) = attempt(conn, fired, &mut started, start, cont) {
+                return Step::Wait(wait);
+            }
+            Step::Ready(if ret.is_null() { Err(conn.last_error()) } else { Ok(()) })
+        }
+    }
+
+    /// `sql` must stay valid until the call completes.
+    pub(super) fn query(sql: *const c_char, len: c_ulong)
+                        -> impl FnMut(&mut Connection, Fired) -> Step<Result<()>> {
+        let mut started = false;
+        let mut ret = 0;
+        move |conn, fired| {
+            let mysql = conn.as_ptr();
+            let out: *mut _ = &mut ret;
+            let start = || unsafe { mysql_real_query_start(out, mysql, sql, len) };
+            let cont = |status| unsafe { mysql_real_query_cont(out, mysql, status) };
+            if let Some(wait) = attempt(conn, fired, &mut started, start, cont) {
+                return Step::Wait(wait);
+            }
+            Step::Ready(if ret == 0 { Ok(()) } else { Err(conn.last_error()) })
+        }
+    }
+
+    type StoreResult = Result<Option<BufferedResult>>;
+
+    pub(super) fn store_result()
+                               -> impl FnMut(&mut Connection, Fired) -> Step<StoreResult> {
+        let mut started = false;
+        let mut res = ptr::null_mut();
+        move |conn, fired| {
+            let mysql = conn.as_ptr();
+            let out: *mut _ = &mut res;
+            let start = || unsafe { mysql_store_result_start(out, mysql) };
+            let cont = |status| unsafe { mysql_store_result_cont(out, mysql, status) };
+            if let Some(wait) = attempt(conn, fired, &mut started, start, cont) {
+                return Step::Wait(wait);
+            }
+            if res.is_null() {
+                Step::Ready(conn.check_no_result().map(|_| None))
+            } else {
+                Step::Ready(Ok(Some(unsafe { BufferedResult::from_raw(res) })))
+            }
+        }
+    }
+
+    pub(super) fn next_result()
+                              -> impl FnMut(&mut Connection, Fired) -> Step<Result<bool>> {
+        let mut started = false;
+        let mut ret = 0;
+        move |conn, fired| {
+            let mysql = conn.as_ptr();
+            let out: *mut _ = &mut ret;
+            let start = || unsafe { mysql_next_result_start(out, mysql) };
+            let cont = |status| unsafe { mysql_next_result_cont(out, mysql, status) };
+            if let Some(wait) = attempt(conn, fired, &mut started, start, cont) {
+                return Step::Wait(wait);
+            }
+            // 0 if there is another result, -1 if not, positive on error.
+            Step::Ready(match ret {
+                0 => Ok(true),
+                -1 => Ok(false),
+                _ => Err(conn.last_error()),
+            })
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! Conversions between `DECIMAL` values and `bigdecimal::BigDecimal`.
+
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+
+use column::Column;
+use decimal::SqlDecimal;
+use decode::{self, DecodeError, FromSqlText};
+use enum_field_types;
+use params::{Param, ToSqlParam};
+
+impl FromSqlText for BigDecimal {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        let plain = decode::checked_decimal::<BigDecimal>(column, value)?;
+        BigDecimal::from_str(&plain).map_err(|_| DecodeError::out_of_range::<BigDecimal>(column))
+    }
+}
+
+impl SqlDecimal for BigDecimal {
+    fn to_plain_string(&self) -> String {
+        BigDecimal::to_plain_string(self)
+    }
+}
+
+/// Sent in plain notation, since the server does not accept exponents in
+/// `DECIMAL` parameters.
+impl ToSqlParam for BigDecimal {
+    fn to_sql_param(&self) -> Param {
+        Param::bytes(enum_field_types::MYSQL_TYPE_NEWDECIMAL,
+                     BigDecimal::to_plain_string(self).into_bytes())
+    }
+}
@@ -0,0 +1,369 @@
+//! Result binding for prepared statements with automatic buffer sizing.
+
+use std::cmp;
+use std::mem;
+use std::os::raw::{c_uint, c_ulong, c_void};
+use std::ptr;
+use std::slice;
+
+use column::{Column, ResultMetadata};
+use datetime::TimeBuffer;
+use error::Result;
+use statement::Statement;
+use {MYSQL_BIND, MYSQL_DATA_TRUNCATED, MYSQL_NO_DATA, MYSQL_TIME, enum_field_types,
+     enum_stmt_attr_type, my_bool, mysql_stmt_attr_set, mysql_stmt_bind_result,
+     mysql_stmt_fetch, mysql_stmt_fetch_column, mysql_stmt_free_result,
+     mysql_stmt_store_result};
+use enum_field_types::*;
+
+/// The output buffer of a single result column.
+///
+/// `length`, `is_null` and `error` are written by the client library through
+/// the pointers in the bound `MYSQL_BIND`, so a `ColumnBuffer` must not move
+/// once bound. `BoundResult` keeps them in a `Vec` that is never resized.
+pub(crate) struct ColumnBuffer {
+    pub(crate) buffer_type: enum_field_types,
+    is_unsigned: bool,
+    /// Streamed columns are bound without a buffer and read on demand with
+    /// a `ColumnReader`, so truncation is expected and not recovered from.
+    streamed: bool,
+    /// `u64` words so that fixed-size values are suitably aligned.
+    data: Vec<u64>,
+    pub(crate) length: c_ulong,
+    pub(crate) is_null: my_bool,
+    error: my_bool,
+}
+
+impl ColumnBuffer {
+    fn new(column: &Column, capacity: usize) -> Self {
+        let mut buffer = ColumnBuffer {
+            buffer_type: column.column_type(),
+            is_unsigned: column.is_unsigned(),
+            streamed: false,
+            data: Vec::new(),
+            length: 0,
+            is_null: 0,
+            error: 0,
+        };
+        buffer.reserve(capacity);
+        buffer
+    }
+
+    fn capacity(&self) -> usize {
+        self.data.len() * mem::size_of::<u64>()
+    }
+
+    fn reserve(&mut self, bytes: usize) {
+        let words = bytes.div_ceil(mem::size_of::<u64>());
+        if words > self.data.len() {
+            self.data.resize(words, 0);
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        let len = cmp::min(self.length as usize, self.capacity());
+        unsafe { slice::from_raw_parts(self.data.as_ptr() as *const u8, len) }
+    }
+
+    fn bind(&mut self) -> MYSQL_BIND {
+        let mut bind: MYSQL_BIND = unsafe { mem::zeroed() };
+        bind.buffer_type = self.buffer_type;
+        bind.is_unsigned = self.is_unsigned as my_bool;
+        bind.buffer = self.data.as_mut_ptr() as *mut c_void;
+        bind.buffer_length = self.capacity() as c_ulong;
+        bind.length = &mut self.length;
+        bind.is_null = &mut self.is_null;
+        bind.error = &mut self.error;
+        bind
+    }
+
+    unsafe fn read<T: Copy>(&self) -> T {
+        ptr::read(self.data.as_ptr() as *const T)
+    }
+}
+
+/// The size of the buffer needed for values of a fixed-size type, or `None`
+/// for variable-length types.
+pub(crate) fn fixed_size(column_type: enum_field_types) -> Option<usize> {
+    match column_type {
+        MYSQL_TYPE_NULL => Some(0),
+        MYSQL_TYPE_TINY => Some(1),
+        MYSQL_TYPE_SHORT | MYSQL_TYPE_YEAR => Some(2),
+        MYSQL_TYPE_LONG | MYSQL_TYPE_INT24 | MYSQL_TYPE_FLOAT => Some(4),
+        MYSQL_TYPE_LONGLONG | MYSQL_TYPE_DOUBLE => Some(8),
+        MYSQL_TYPE_DATE | MYSQL_TYPE_NEWDATE | MYSQL_TYPE_TIME | MYSQL_TYPE_TIME2 |
+        MYSQL_TYPE_DATETIME | MYSQL_TYPE_DATETIME2 | MYSQL_TYPE_TIMESTAMP |
+        MYSQL_TYPE_TIMESTAMP2 => Some(mem::size_of::<TimeBuffer>()),
+        _ => None,
+    }
+}
+
+/// A decoded binary protocol value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value<'a> {
+    Null,
+    Int(i64),
+    UInt(u64),
+    Float(f32),
+    Double(f64),
+    Time(MYSQL_TIME),
+    /// Strings, blobs, `DECIMAL`, `BIT`, `JSON` and other variable-length
+    /// values.
+    Bytes(&'a [u8]),
+}
+
+/// Options controlling how `mysql_stmt_bind_result` buffers are sized.
+#[derive(Debug, Clone)]
+pub struct ResultBinder {
+    store: bool,
+    update_max_length: bool,
+    initial_capacity: usize,
+    streamed: Vec<usize>,
+}
+
+impl Default for ResultBinder {
+    fn default() -> Self {
+        ResultBinder {
+            store: false,
+            update_max_length: false,
+            initial_capacity: 256,
+            streamed: Vec::new(),
+        }
+    }
+}
+
+impl ResultBinder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the whole result set into client memory with
+    /// `mysql_stmt_store_result` before binding.
+    pub fn store(mut self, store: bool) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Enables `STMT_ATTR_UPDATE_MAX_LENGTH` and stores the result, so that
+    /// variable-length buffers can be allocated at exactly the length of the
+    /// longest value and never need to be grown.
+    pub fn exact_lengths(mut self, exact: bool) -> Self {
+        self.update_max_length = exact;
+        self.store |= exact;
+        self
+    }
+
+    /// The initial buffer size for variable-length columns whose maximum
+    /// length is not known. Defaults to 256 bytes.
+    pub fn initial_capacity(mut self, bytes: usize) -> Self {
+        self.initial_capacity = bytes;
+        self
+    }
+
+    /// Binds column `idx` without a buffer, so that its values are only read
+    /// on demand through `BoundResult::column_reader`. Use this for large
+    /// `BLOB` and `TEXT` columns that should not be held in memory.
+    pub fn stream_column(mut self, idx: usize) -> Self {
+        self.streamed.push(idx);
+        self
+    }
+
+    /// Binds output buffers for the result set of an executed statement.
+    ///
+    /// Returns `Ok(None)` if the statement does not produce a result set.
+    pub fn bind<'stmt, 'conn>(&self, stmt: &'stmt mut Statement<'conn>)
+                              -> Result<Option<BoundResult<'stmt, 'conn>>> {
+        if self.update_max_length {
+            let on: my_bool = 1;
+            let attr = enum_stmt_attr_type::STMT_ATTR_UPDATE_MAX_LENGTH;
+            let on_ptr = &on as *const my_bool as *const c_void;
+            if unsafe { mysql_stmt_attr_set(stmt.as_ptr(), attr, on_ptr) } != 0 {
+                return Err(stmt.last_error());
+            }
+        }
+        if self.store && unsafe { mysql_stmt_store_result(stmt.as_ptr()) } != 0 {
+            return Err(stmt.last_error());
+        }
+        let metadata = match stmt.result_metadata()? {
+            Some(metadata) => metadata,
+            None => return Ok(None),
+        };
+        let buffers = metadata.columns()
+            .iter()
+            .enumerate()
+            .map(|(idx, column)| {
+                let streamed = self.streamed.contains(&idx);
+                let capacity = match fixed_size(column.column_type()) {
+                    Some(size) => size,
+                    None if streamed => 0,
+                    None if self.update_max_length => column.max_length() as usize,
+                    None => {
+                        cmp::min(column.display_length() as usize, self.initial_capacity)
+                    }
+                };
+                let mut buffer = ColumnBuffer::new(column, capacity);
+                buffer.streamed = streamed && fixed_size(column.column_type()).is_none();
+                buffer
+            })
+            .collect();
+        let mut result = BoundResult {
+            stmt,
+            metadata,
+            buffers,
+            binds: Vec::new(),
+        };
+        result.bind()?;
+        Ok(Some(result))
+    }
+}
+
+/// The result set of a prepared statement with bound output buffers.
+///
+/// Rows are fetched with `next_row`. Dropping the result frees it with
+/// `mysql_stmt_free_result`.
+pub struct BoundResult<'stmt, 'conn> {
+    pub(crate) stmt: &'stmt mut Statement<'conn>,
+    metadata: ResultMetadata,
+    pub(crate) buffers: Vec<ColumnBuffer>,
+    binds: Vec<MYSQL_BIND>,
+}
+
+impl<'stmt, 'conn> BoundResult<'stmt, 'conn> {
+    pub fn columns(&self) -> &[Column] {
+        self.metadata.columns()
+    }
+
+    fn bind(&mut self) -> Result<()> {
+        self.binds = self.buffers.iter_mut().map(ColumnBuffer::bind).collect();
+        if unsafe { mysql_stmt_bind_result(self.stmt.as_ptr(), self.binds.as_mut_ptr()) } != 0 {
+            return Err(self.stmt.last_error());
+        }
+        Ok(())
+    }
+
+    /// Fetches the next row.
+    ///
+    /// Columns that did not fit into their buffer are grown to the reported
+    /// length and read again with `mysql_stmt_fetch_column`, so the returned
+    /// row is never truncated.
+    pub fn next_row(&mut self) -> Result<Option<BoundRow<'_>>> {
+        let rc = unsafe { mysql_stmt_fetch(self.stmt.as_ptr()) };
+        match rc as c_uint {
+            0 => {}
+            MYSQL_NO_DATA => return Ok(None),
+            MYSQL_DATA_TRUNCATED => self.refetch_truncated()?,
+            _ => return Err(self.stmt.last_error()),
+        }
+        Ok(Some(BoundRow {
+            columns: self.metadata.columns(),
+            buffers: &self.buffers,
+        }))
+    }
+
+    fn refetch_truncated(&mut self) -> Result<()> {
+        let mut grown = false;
+        for (idx, buffer) in self.buffers.iter_mut().enumerate() {
+            let length = buffer.length as usize;
+            if buffer.streamed || buffer.error == 0 || length <= buffer.capacity() {
+                continue;
+            }
+            buffer.reserve(length);
+            let mut bind = buffer.bind();
+            let rc = unsafe {
+                mysql_stmt_fetch_column(self.stmt.as_ptr(), &mut bind, idx as c_uint, 0)
+            };
+            if rc != 0 {
+                return Err(self.stmt.last_error());
+            }
+            buffer.error = 0;
+            grown = true;
+        }
+        // The library holds on to the old buffer pointers until told
+        // otherwise.
+        if grown {
+            self.bind()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'stmt, 'conn> Drop for BoundResult<'stmt, 'conn> {
+    fn drop(&mut self) {
+        unsafe {
+            mysql_stmt_free_result(self.stmt.as_ptr());
+        }
+    }
+}
+
+/// A row fetched from a `BoundResult`, borrowing its buffers.
+#[derive(Clone, Copy)]
+pub struct BoundRow<'a> {
+    columns: &'a [Column],
+    buffers: &'a [ColumnBuffer],
+}
+
+impl<'a> BoundRow<'a> {
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    pub fn columns(&self) -> &'a [Column] {
+        self.columns
+    }
+
+    pub fn is_null(&self, idx: usize) -> bool {
+        self.buffers[idx].is_null != 0
+    }
+
+    /// Returns the value of column `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn get(&self, idx: usize) -> Value<'a> {
+        let buffer = &self.buffers[idx];
+        if buffer.is_null != 0 {
+            return Value::Null;
+        }
+        unsafe {
+            match (buffer.buffer_type, buffer.is_unsigned) {
+                (MYSQL_TYPE_NULL, _) => Value::Null,
+                (MYSQL_TYPE_TINY, false) => Value::Int(buffer.read::<i8>() as i64),
+                (MYSQL_TYPE_TINY, true) => Value::UInt(buffer.read::<u8>() as u64),
+                (MYSQL_TYPE_SHORT, false) => Value::Int(buffer.read::<i16>() as i64),
+                (MYSQL_TYPE_SHORT, true) | (MYSQL_TYPE_YEAR, _) => {
+                    Value::UInt(buffer.read::<u16>() as u64)
+                }
+                (MYSQL_TYPE_LONG, false) | (MYSQL_TYPE_INT24, false) => {
+                    Value::Int(buffer.read::<i32>() as i64)
+                }
+                (MYSQL_TYPE_LONG, true) | (MYSQL_TYPE_INT24, true) => {
+                    Value::UInt(buffer.read::<u32>() as u64)
+                }
+                (MYSQL_TYPE_LONGLONG, false) => Value::Int(buffer.read::<i64>()),
+                (MYSQL_TYPE_LONGLONG, true) => Value::UInt(buffer.read::<u64>()),
+                (MYSQL_TYPE_FLOAT, _) => Value::Float(buffer.read::<f32>()),
+                (MYSQL_TYPE_DOUBLE, _) => Value::Double(buffer.read::<f64>()),
+                (ty, _) if fixed_size(ty) == Some(mem::size_of::<TimeBuffer>()) => {
+                    Value::Time(buffer.read::<TimeBuffer>().time)
+                }
+                _ => Value::Bytes(buffer.bytes()),
+            }
+        }
+    }
+
+    /// Returns the raw contents of the buffer bound to column `idx`, or
+    /// `None` if the value is `NULL`.
+    pub fn bytes(&self, idx: usize) -> Option<&'a [u8]> {
+        let buffer = &self.buffers[idx];
+        if buffer.is_null != 0 {
+            None
+        } else {
+            Some(buffer.bytes())
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Cancellation of running queries from other threads.
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_void};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use connection::{ConnectOptions, Connection};
+use error::Result;
+use handle_info;
+use {my_bool, mysql_option, mysql_options};
+
+/// A handle that cancels the statement currently running on a connection
+/// by sending `KILL QUERY` over a separate connection.
+///
+/// The handle connects with the options the connection was opened with, or
+/// for handles adopted with `Connection::from_raw`, with the host, port,
+/// socket and credentials read from the handle. It targets the connection's
+/// server thread id.
+#[derive(Debug, Clone)]
+pub struct CancelHandle {
+    opts: ConnectOptions,
+    thread_id: Arc<AtomicU64>,
+}
+
+impl Connection {
+    /// Creates a handle that cancels statements running on this connection.
+    ///
+    /// This turns off automatic reconnection for the rest of the
+    /// connection's life: a reconnect in the middle of a call would move
+    /// the connection to a new server thread, and leave the handle
+    /// targeting one that may since serve another client. A lost
+    /// connection then fails with `CR_SERVER_LOST` instead.
+    ///
+    /// When `AsyncConnection` drives MariaDB's nonblocking API, the password
+    /// of an adopted handle cannot be read, and the side connection is
+    /// opened without one.
+    pub fn cancel_handle(&mut self) -> CancelHandle {
+        let reconnect: my_bool = 0;
+        unsafe {
+            mysql_options(self.as_ptr(),
+                          mysql_option::MYSQL_OPT_RECONNECT,
+                          &reconnect as *const my_bool as *const c_void);
+        }
+        let opts = self.opts.clone().unwrap_or_else(|| self.endpoint_options());
+        CancelHandle {
+            opts,
+            thread_id: self.thread_id.clone(),
+        }
+    }
+
+    fn endpoint_options(&self) -> ConnectOptions {
+        let raw = unsafe { handle_info::endpoint(self.as_ptr()) };
+        let mut opts = ConnectOptions::new().port(raw.port as u16);
+        if let Some(host) = unsafe { owned_string(raw.host) } {
+            opts = opts.host(host);
+        }
+        if let Some(user) = unsafe { owned_string(raw.user) } {
+            opts = opts.user(user);
+        }
+        if let Some(password) = unsafe { owned_string(raw.passwd) } {
+            opts = opts.password(password);
+        }
+        if let Some(socket) = unsafe { owned_string(raw.unix_socket) } {
+            opts = opts.unix_socket(socket);
+        }
+        opts
+    }
+}
+
+impl CancelHandle {
+    /// The server thread id that `cancel` will target.
+    pub fn thread_id(&self) -> u64 {
+        self.thread_id.load(Ordering::Relaxed)
+    }
+
+    /// Kills the statement the connection is executing, if any. The
+    /// connection itself stays open, and the interrupted call fails with
+    /// `ER_QUERY_INTERRUPTED`.
+    ///
+    /// Other client options of the original connection, such as TLS
+    /// settings, are not captured; the side connection uses the client
+    /// library's defaults and option files.
+    pub fn cancel(&self) -> Result<()> {
+        let mut conn = Connection::connect(&self.opts)?;
+        conn.query(&format!("KILL QUERY {}", self.thread_id()))
+    }
+}
+
+pub(crate) unsafe fn owned_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+    }
+}
@@ -0,0 +1,764 @@
+//! Character set and collation metadata.
+//!
+//! `Connection::charset` describes the connection's character set as
+//! reported by the client library. Collation ids, such as the `charsetnr`
+//! of a column, are resolved offline with `mysql_collation` and
+//! `mariadb_collation`, since the two servers assign ids differently above
+//! 247.
+
+use std::mem;
+
+use cancel::owned_string;
+use connection::Connection;
+use {BINARY_CHARSET_NR, MY_CHARSET_INFO, mysql_get_character_set_info};
+
+/// The connection's character set, from `mysql_get_character_set_info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharsetInfo {
+    /// The id of the collation in use.
+    pub number: u32,
+    /// The character set name, such as `utf8mb4`.
+    pub name: String,
+    /// The collation name, such as `utf8mb4_general_ci`.
+    pub collation: String,
+    /// A description of the character set, such as `UTF-8 Unicode`.
+    pub comment: String,
+    /// The directory the character set was loaded from, if it is not
+    /// compiled into the client library.
+    pub dir: Option<String>,
+    /// The minimum length of a character in bytes.
+    pub mbminlen: u32,
+    /// The maximum length of a character in bytes.
+    pub mbmaxlen: u32,
+}
+
+impl Connection {
+    /// The character set the client library uses for the connection, such
+    /// as when escaping strings. It follows `mysql_set_character_set`, but
+    /// may miss a change made with a `SET NAMES` statement.
+    pub fn charset(&self) -> CharsetInfo {
+        let mut info: MY_CHARSET_INFO = unsafe { mem::zeroed() };
+        unsafe { mysql_get_character_set_info(self.as_ptr(), &mut info) };
+        CharsetInfo {
+            number: info.number,
+            name: unsafe { owned_string(info.csname) }.unwrap_or_default(),
+            collation: unsafe { owned_string(info.name) }.unwrap_or_default(),
+            comment: unsafe { owned_string(info.comment) }.unwrap_or_default(),
+            dir: unsafe { owned_string(info.dir) },
+            mbminlen: info.mbminlen,
+            mbmaxlen: info.mbmaxlen,
+        }
+    }
+}
+
+/// A collation known to a server, identified by the id sent in column
+/// metadata and the handshake.
+///
+/// Names follow current server versions, which call the three-byte UTF-8
+/// character set `utf8mb3` where older ones say `utf8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Collation {
+    pub id: u32,
+    pub charset: &'static str,
+    pub name: &'static str,
+}
+
+impl Collation {
+    /// Whether values in this collation are raw bytes rather than text.
+    pub fn is_binary(&self) -> bool {
+        self.id == BINARY_CHARSET_NR
+    }
+}
+
+/// Looks up a collation id as assigned by MySQL, up to 8.0.
+pub fn mysql_collation(id: u32) -> Option<Collation> {
+    find(MYSQL_COLLATIONS, id)
+}
+
+/// Looks up a collation id as assigned by MariaDB, including the `NO PAD`
+/// collations added in 10.2. The UCA 14.0 collations of 10.10 and later
+/// have no fixed ids and are not included.
+pub fn mariadb_collation(id: u32) -> Option<Collation> {
+    find(MARIADB_COLLATIONS, id)
+}
+
+fn find(table: &[Collation], id: u32) -> Option<Collation> {
+    table.binary_search_by_key(&id, |collation| collation.id).ok().map(|idx| table[idx])
+}
+
+static MYSQL_COLLATIONS: &[Collation] = &[
+    Collation { id: 1, charset: "big5", name: "big5_chinese_ci" },
+    Collation { id: 2, charset: "latin2", name: "latin2_czech_cs" },
+    Collation { id: 3, charset: "dec8", name: "dec8_swedish_ci" },
+    Collation { id: 4, charset: "cp850", name: "cp850_general_ci" },
+    Collation { id: 5, charset: "latin1", name: "latin1_german1_ci" },
+    Collation { id: 6, charset: "hp8", name: "hp8_english_ci" },
+    Collation { id: 7, charset: "koi8r", name: "koi8r_general_ci" },
+    Collation { id: 8, charset: "latin1", name: "latin1_swedish_ci" },
+    Collation { id: 9, charset: "latin2", name: "latin2_general_ci" },
+    Collation { id: 10, charset: "swe7", name: "swe7_swedish_ci" },
+    Collation { id: 11, charset: "ascii", name: "ascii_general_ci" },
+    Collation { id: 12, charset: "ujis", name: "ujis_japanese_ci" },
+    Collation { id: 13, charset: "sjis", name: "sjis_japanese_ci" },
+    Collation { id: 14, charset: "cp1251", name: "cp1251_bulgarian_ci" },
+    Collation { id: 15, charset: "latin1", name: "latin1_danish_ci" },
+    Collation { id: 16, charset: "hebrew", name: "hebrew_general_ci" },
+    Collation { id: 18, charset: "tis620", name: "tis620_thai_ci" },
+    Collation { id: 19, charset: "euckr", name: "euckr_korean_ci" },
+    Collation { id: 20, charset: "latin7", name: "latin7_estonian_cs" },
+    Collation { id: 21, charset: "latin2", name: "latin2_hungarian_ci" },
+    Collation { id: 22, charset: "koi8u", name: "koi8u_general_ci" },
+    Collation { id: 23, charset: "cp1251", name: "cp1251_ukrainian_ci" },
+    Collation { id: 24, charset: "gb2312", name: "gb2312_chinese_ci" },
+    Collation { id: 25, charset: "greek", name: "greek_general_ci" },
+    Collation { id: 26, charset: "cp1250", name: "cp1250_general_ci" },
+    Collation { id: 27, charset: "latin2", name: "latin2_croatian_ci" },
+    Collation { id: 28, charset: "gbk", name: "gbk_chinese_ci" },
+    Collation { id: 29, charset: "cp1257", name: "cp1257_lithuanian_ci" },
+    Collation { id: 30, charset: "latin5", name: "latin5_turkish_ci" },
+    Collation { id: 31, charset: "latin1", name: "latin1_german2_ci" },
+    Collation { id: 32, charset: "armscii8", name: "armscii8_general_ci" },
+    Collation { id: 33, charset: "utf8mb3", name: "utf8mb3_general_ci" },
+    Collation { id: 34, charset: "cp1250", name: "cp1250_czech_cs" },
+    Collation { id: 35, charset: "ucs2", name: "ucs2_general_ci" },
+    Collation { id: 36, charset: "cp866", name: "cp866_general_ci" },
+    Collation { id: 37, charset: "keybcs2", name: "keybcs2_general_ci" },
+    Collation { id: 38, charset: "macce", name: "macce_general_ci" },
+    Collation { id: 39, charset: "macroman", name: "macroman_general_ci" },
+    Collation { id: 40, charset: "cp852", name: "cp852_general_ci" },
+    Collation { id: 41, charset: "latin7", name: "latin7_general_ci" },
+    Collation { id: 42, charset: "latin7", name: "latin7_general_cs" },
+    Collation { id: 43, charset: "macce", name: "macce_bin" },
+    Collation { id: 44, charset: "cp1250", name: "cp1250_croatian_ci" },
+    Collation { id: 45, charset: "utf8mb4", name: "utf8mb4_general_ci" },
+    Collation { id: 46, charset: "utf8mb4", name: "utf8mb4_bin" },
+    Collation { id: 47, charset: "latin1", name: "latin1_bin" },
+    Collation { id: 48, charset: "latin1", name: "latin1_general_ci" },
+    Collation { id: 49, charset: "latin1", name: "latin1_general_cs" },
+    Collation { id: 50, charset: "cp1251", name: "cp1251_bin" },
+    Collation { id: 51, charset: "cp1251", name: "cp1251_general_ci" },
+    Collation { id: 52, charset: "cp1251", name: "cp1251_general_cs" },
+    Collation { id: 53, charset: "macroman", name: "macroman_bin" },
+    Collation { id: 54, charset: "utf16", name: "utf16_general_ci" },
+    Collation { id: 55, charset: "utf16", name: "utf16_bin" },
+    Collation { id: 56, charset: "utf16le", name: "utf16le_general_ci" },
+    Collation { id: 57, charset: "cp1256", name: "cp1256_general_ci" },
+    Collation { id: 58, charset: "cp1257", name: "cp1257_bin" },
+    Collation { id: 59, charset: "cp1257", name: "cp1257_general_ci" },
+    Collation { id: 60, charset: "utf32", name: "utf32_general_ci" },
+    Collation { id: 61, charset: "utf32", name: "utf32_bin" },
+    Collation { id: 62, charset: "utf16le", name: "utf16le_bin" },
+    Collation { id: 63, charset: "binary", name: "binary" },
+    Collation { id: 64, charset: "armscii8", name: "armscii8_bin" },
+    Collation { id: 65, charset: "ascii", name: "ascii_bin" },
+    Collation { id: 66, charset: "cp1250", name: "cp1250_bin" },
+    Collation { id: 67, charset: "cp1256", name: "cp1256_bin" },
+    Collation { id: 68, charset: "cp866", name: "cp866_bin" },
+    Collation { id: 69, charset: "dec8", name: "dec8_bin" },
+    Collation { id: 70, charset: "greek", name: "greek_bin" },
+    Collation { id: 71, charset: "hebrew", name: "hebrew_bin" },
+    Collation { id: 72, charset: "hp8", name: "hp8_bin" },
+    Collation { id: 73, charset: "keybcs2", name: "keybcs2_bin" },
+    Collation { id: 74, charset: "koi8r", name: "koi8r_bin" },
+    Collation { id: 75, charset: "koi8u", name: "koi8u_bin" },
+    Collation { id: 76, charset: "utf8mb3", name: "utf8mb3_tolower_ci" },
+    Collation { id: 77, charset: "latin2", name: "latin2_bin" },
+    Collation { id: 78, charset: "latin5", name: "latin5_bin" },
+    Collation { id: 79, charset: "latin7", name: "latin7_bin" },
+    Collation { id: 80, charset: "cp850", name: "cp850_bin" },
+    Collation { id: 81, charset: "cp852", name: "cp852_bin" },
+    Collation { id: 82, charset: "swe7", name: "swe7_bin" },
+    Collation { id: 83, charset: "utf8mb3", name: "utf8mb3_bin" },
+    Collation { id: 84, charset: "big5", name: "big5_bin" },
+    Collation { id: 85, charset: "euckr", name: "euckr_bin" },
+    Collation { id: 86, charset: "gb2312", name: "gb2312_bin" },
+    Collation { id: 87, charset: "gbk", name: "gbk_bin" },
+    Collation { id: 88, charset: "sjis", name: "sjis_bin" },
+    Collation { id: 89, charset: "tis620", name: "tis620_bin" },
+    Collation { id: 90, charset: "ucs2", name: "ucs2_bin" },
+    Collation { id: 91, charset: "ujis", name: "ujis_bin" },
+    Collation { id: 92, charset: "geostd8", name: "geostd8_general_ci" },
+    Collation { id: 93, charset: "geostd8", name: "geostd8_bin" },
+    Collation { id: 94, charset: "latin1", name: "latin1_spanish_ci" },
+    Collation { id: 95, charset: "cp932", name: "cp932_japanese_ci" },
+    Collation { id: 96, charset: "cp932", name: "cp932_bin" },
+    Collation { id: 97, charset: "eucjpms", name: "eucjpms_japanese_ci" },
+    Collation { id: 98, charset: "eucjpms", name: "eucjpms_bin" },
+    Collation { id: 99, charset: "cp1250", name: "cp1250_polish_ci" },
+    Collation { id: 101, charset: "utf16", name: "utf16_unicode_ci" },
+    Collation { id: 102, charset: "utf16", name: "utf16_icelandic_ci" },
+    Collation { id: 103, charset: "utf16", name: "utf16_latvian_ci" },
+    Collation { id: 104, charset: "utf16", name: "utf16_romanian_ci" },
+    Collation { id: 105, charset: "utf16", name: "utf16_slovenian_ci" },
+    Collation { id: 106, charset: "utf16", name: "utf16_polish_ci" },
+    Collation { id: 107, charset: "utf16", name: "utf16_estonian_ci" },
+    Collation { id: 108, charset: "utf16", name: "utf16_spanish_ci" },
+    Collation { id: 109, charset: "utf16", name: "utf16_swedish_ci" },
+    Collation { id: 110, charset: "utf16", name: "utf16_turkish_ci" },
+    Collation { id: 111, charset: "utf16", name: "utf16_czech_ci" },
+    Collation { id: 112, charset: "utf16", name: "utf16_danish_ci" },
+    Collation { id: 113, charset: "utf16", name: "utf16_lithuanian_ci" },
+    Collation { id: 114, charset: "utf16", name: "utf16_slovak_ci" },
+    Collation { id: 115, charset: "utf16", name: "utf16_spanish2_ci" },
+    Collation { id: 116, charset: "utf16", name: "utf16_roman_ci" },
+    Collation { id: 117, charset: "utf16", name: "utf16_persian_ci" },
+    Collation { id: 118, charset: "utf16", name: "utf16_esperanto_ci" },
+    Collation { id: 119, charset: "utf16", name: "utf16_hungarian_ci" },
+    Collation { id: 120, charset: "utf16", name: "utf16_sinhala_ci" },
+    Collation { id: 121, charset: "utf16", name: "utf16_german2_ci" },
+    Collation { id: 122, charset: "utf16", name: "utf16_croatian_ci" },
+    Collation { id: 123, charset: "utf16", name: "utf16_unicode_520_ci" },
+    Collation { id: 124, charset: "utf16", name: "utf16_vietnamese_ci" },
+    Collation { id: 128, charset: "ucs2", name: "ucs2_unicode_ci" },
+    Collation { id: 129, charset: "ucs2", name: "ucs2_icelandic_ci" },
+    Collation { id: 130, charset: "ucs2", name: "ucs2_latvian_ci" },
+    Collation { id: 131, charset: "ucs2", name: "ucs2_romanian_ci" },
+    Collation { id: 132, charset: "ucs2", name: "ucs2_slovenian_ci" },
+    Collation { id: 133, charset: "ucs2", name: "ucs2_polish_ci" },
+    Collation { id: 134, charset: "ucs2", name: "ucs2_estonian_ci" },
+    Collation { id: 135, charset: "ucs2", name: "ucs2_spanish_ci" },
+    Collation { id: 136, charset: "ucs2", name: "ucs2_swedish_ci" },
+    Collation { id: 137, charset: "ucs2", name: "ucs2_turkish_ci" },
+    Collation { id: 138, charset: "ucs2", name: "ucs2_czech_ci" },
+    Collation { id: 139, charset: "ucs2", name: "ucs2_danish_ci" },
+    Collation { id: 140, charset: "ucs2", name: "ucs2_lithuanian_ci" },
+    Collation { id: 141, charset: "ucs2", name: "ucs2_slovak_ci" },
+    Collation { id: 142, charset: "ucs2", name: "ucs2_spanish2_ci" },
+    Collation { id: 143, charset: "ucs2", name: "ucs2_roman_ci" },
+    Collation { id: 144, charset: "ucs2", name: "ucs2_persian_ci" },
+    Collation { id: 145, charset: "ucs2", name: "ucs2_esperanto_ci" },
+    Collation { id: 146, charset: "ucs2", name: "ucs2_hungarian_ci" },
+    Collation { id: 147, charset: "ucs2", name: "ucs2_sinhala_ci" },
+    Collation { id: 148, charset: "ucs2", name: "ucs2_german2_ci" },
+    Collation { id: 149, charset: "ucs2", name: "ucs2_croatian_ci" },
+    Collation { id: 150, charset: "ucs2", name: "ucs2_unicode_520_ci" },
+    Collation { id: 151, charset: "ucs2", name: "ucs2_vietnamese_ci" },
+    Collation { id: 159, charset: "ucs2", name: "ucs2_general_mysql500_ci" },
+    Collation { id: 160, charset: "utf32", name: "utf32_unicode_ci" },
+    Collation { id: 161, charset: "utf32", name: "utf32_icelandic_ci" },
+    Collation { id: 162, charset: "utf32", name: "utf32_latvian_ci" },
+    Collation { id: 163, charset: "utf32", name: "utf32_romanian_ci" },
+    Collation { id: 164, charset: "utf32", name: "utf32_slovenian_ci" },
+    Collation { id: 165, charset: "utf32", name: "utf32_polish_ci" },
+    Collation { id: 166, charset: "utf32", name: "utf32_estonian_ci" },
+    Collation { id: 167, charset: "utf32", name: "utf32_spanish_ci" },
+    Collation { id: 168, charset: "utf32", name: "utf32_swedish_ci" },
+    Collation { id: 169, charset: "utf32", name: "utf32_turkish_ci" },
+    Collation { id: 170, charset: "utf32", name: "utf32_czech_ci" },
+    Collation { id: 171, charset: "utf32", name: "utf32_danish_ci" },
+    Collation { id: 172, charset: "utf32", name: "utf32_lithuanian_ci" },
+    Collation { id: 173, charset: "utf32", name: "utf32_slovak_ci" },
+    Collation { id: 174, charset: "utf32", name: "utf32_spanish2_ci" },
+    Collation { id: 175, charset: "utf32", name: "utf32_roman_ci" },
+    Collation { id: 176, charset: "utf32", name: "utf32_persian_ci" },
+    Collation { id: 177, charset: "utf32", name: "utf32_esperanto_ci" },
+    Collation { id: 178, charset: "utf32", name: "utf32_hungarian_ci" },
+    Collation { id: 179, charset: "utf32", name: "utf32_sinhala_ci" },
+    Collation { id: 180, charset: "utf32", name: "utf32_german2_ci" },
+    Collation { id: 181, charset: "utf32", name: "utf32_croatian_ci" },
+    Collation { id: 182, charset: "utf32", name: "utf32_unicode_520_ci" },
+    Collation { id: 183, charset: "utf32", name: "utf32_vietnamese_ci" },
+    Collation { id: 192, charset: "utf8mb3", name: "utf8mb3_unicode_ci" },
+    Collation { id: 193, charset: "utf8mb3", name: "utf8mb3_icelandic_ci" },
+    Collation { id: 194, charset: "utf8mb3", name: "utf8mb3_latvian_ci" },
+    Collation { id: 195, charset: "utf8mb3", name: "utf8mb3_romanian_ci" },
+    Collation { id: 196, charset: "utf8mb3", name: "utf8mb3_slovenian_ci" },
+    Collation { id: 197, charset: "utf8mb3", name: "utf8mb3_polish_ci" },
+    Collation { id: 198, charset: "utf8mb3", name: "utf8mb3_estonian_ci" },
+    Collation { id: 199, charset: "utf8mb3", name: "utf8mb3_spanish_ci" },
+    Collation { id: 200, charset: "utf8mb3", name: "utf8mb3_swedish_ci" },
+    Collation { id: 201, charset: "utf8mb3", name: "utf8mb3_turkish_ci" },
+    Collation { id: 202, charset: "utf8mb3", name: "utf8mb3_czech_ci" },
+    Collation { id: 203, charset: "utf8mb3", name: "utf8mb3_danish_ci" },
+    Collation { id: 204, charset: "utf8mb3", name: "utf8mb3_lithuanian_ci" },
+    Collation { id: 205, charset: "utf8mb3", name: "utf8mb3_slovak_ci" },
+    Collation { id: 206, charset: "utf8mb3", name: "utf8mb3_spanish2_ci" },
+    Collation { id: 207, charset: "utf8mb3", name: "utf8mb3_roman_ci" },
+    Collation { id: 208, charset: "utf8mb3", name: "utf8mb3_persian_ci" },
+    Collation { id: 209, charset: "utf8mb3", name: "utf8mb3_esperanto_ci" },
+    Collation { id: 210, charset: "utf8mb3", name: "utf8mb3_hungarian_ci" },
+    Collation { id: 211, charset: "utf8mb3", name: "utf8mb3_sinhala_ci" },
+    Collation { id: 212, charset: "utf8mb3", name: "utf8mb3_german2_ci" },
+    Collation { id: 213, charset: "utf8mb3", name: "utf8mb3_croatian_ci" },
+    Collation { id: 214, charset: "utf8mb3", name: "utf8mb3_unicode_520_ci" },
+    Collation { id: 215, charset: "utf8mb3", name: "utf8mb3_vietnamese_ci" },
+    Collation { id: 223, charset: "utf8mb3", name: "utf8mb3_general_mysql500_ci" },
+    Collation { id: 224, charset: "utf8mb4", name: "utf8mb4_unicode_ci" },
+    Collation { id: 225, charset: "utf8mb4", name: "utf8mb4_icelandic_ci" },
+    Collation { id: 226, charset: "utf8mb4", name: "utf8mb4_latvian_ci" },
+    Collation { id: 227, charset: "utf8mb4", name: "utf8mb4_romanian_ci" },
+    Collation { id: 228, charset: "utf8mb4", name: "utf8mb4_slovenian_ci" },
+    Collation { id: 229, charset: "utf8mb4", name: "utf8mb4_polish_ci" },
+    Collation { id: 230, charset: "utf8mb4", name: "utf8mb4_estonian_ci" },
+    Collation { id: 231, charset: "utf8mb4", name: "utf8mb4_spanish_ci" },
+    Collation { id: 232, charset: "utf8mb4", name: "utf8mb4_swedish_ci" },
+    Collation { id: 233, charset: "utf8mb4", name: "utf8mb4_turkish_ci" },
+    Collation { id: 234, charset: "utf8mb4", name: "utf8mb4_czech_ci" },
+    Collation { id: 235, charset: "utf8mb4", name: "utf8mb4_danish_ci" },
+    Collation { id: 236, charset: "utf8mb4", name: "utf8mb4_lithuanian_ci" },
+    Collation { id: 237, charset: "utf8mb4", name: "utf8mb4_slovak_ci" },
+    Collation { id: 238, charset: "utf8mb4", name: "utf8mb4_spanish2_ci" },
+    Collation { id: 239, charset: "utf8mb4", name: "utf8mb4_roman_ci" },
+    Collation { id: 240, charset: "utf8mb4", name: "utf8mb4_persian_ci" },
+    Collation { id: 241, charset: "utf8mb4", name: "utf8mb4_esperanto_ci" },
+    Collation { id: 242, charset: "utf8mb4", name: "utf8mb4_hungarian_ci" },
+    Collation { id: 243, charset: "utf8mb4", name: "utf8mb4_sinhala_ci" },
+    Collation { id: 244, charset: "utf8mb4", name: "utf8mb4_german2_ci" },
+    Collation { id: 245, charset: "utf8mb4", name: "utf8mb4_croatian_ci" },
+    Collation { id: 246, charset: "utf8mb4", name: "utf8mb4_unicode_520_ci" },
+    Collation { id: 247, charset: "utf8mb4", name: "utf8mb4_vietnamese_ci" },
+    Collation { id: 248, charset: "gb18030", name: "gb18030_chinese_ci" },
+    Collation { id: 249, charset: "gb18030", name: "gb18030_bin" },
+    Collation { id: 250, charset: "gb18030", name: "gb18030_unicode_520_ci" },
+    Collation { id: 255, charset: "utf8mb4", name: "utf8mb4_0900_ai_ci" },
+    Collation { id: 256, charset: "utf8mb4", name: "utf8mb4_de_pb_0900_ai_ci" },
+    Collation { id: 257, charset: "utf8mb4", name: "utf8mb4_is_0900_ai_ci" },
+    Collation { id: 258, charset: "utf8mb4", name: "utf8mb4_lv_0900_ai_ci" },
+    Collation { id: 259, charset: "utf8mb4", name: "utf8mb4_ro_0900_ai_ci" },
+    Collation { id: 260, charset: "utf8mb4", name: "utf8mb4_sl_0900_ai_ci" },
+    Collation { id: 261, charset: "utf8mb4", name: "utf8mb4_pl_0900_ai_ci" },
+    Collation { id: 262, charset: "utf8mb4", name: "utf8mb4_et_0900_ai_ci" },
+    Collation { id: 263, charset: "utf8mb4", name: "utf8mb4_es_0900_ai_ci" },
+    Collation { id: 264, charset: "utf8mb4", name: "utf8mb4_sv_0900_ai_ci" },
+    Collation { id: 265, charset: "utf8mb4", name: "utf8mb4_tr_0900_ai_ci" },
+    Collation { id: 266, charset: "utf8mb4", name: "utf8mb4_cs_0900_ai_ci" },
+    Collation { id: 267, charset: "utf8mb4", name: "utf8mb4_da_0900_ai_ci" },
+    Collation { id: 268, charset: "utf8mb4", name: "utf8mb4_lt_0900_ai_ci" },
+    Collation { id: 269, charset: "utf8mb4", name: "utf8mb4_sk_0900_ai_ci" },
+    Collation { id: 270, charset: "utf8mb4", name: "utf8mb4_es_trad_0900_ai_ci" },
+    Collation { id: 271, charset: "utf8mb4", name: "utf8mb4_la_0900_ai_ci" },
+    Collation { id: 273, charset: "utf8mb4", name: "utf8mb4_eo_0900_ai_ci" },
+    Collation { id: 274, charset: "utf8mb4", name: "utf8mb4_hu_0900_ai_ci" },
+    Collation { id: 275, charset: "utf8mb4", name: "utf8mb4_hr_0900_ai_ci" },
+    Collation { id: 277, charset: "utf8mb4", name: "utf8mb4_vi_0900_ai_ci" },
+    Collation { id: 278, charset: "utf8mb4", name: "utf8mb4_0900_as_cs" },
+    Collation { id: 279, charset: "utf8mb4", name: "utf8mb4_de_pb_0900_as_cs" },
+    Collation { id: 280, charset: "utf8mb4", name: "utf8mb4_is_0900_as_cs" },
+    Collation { id: 281, charset: "utf8mb4", name: "utf8mb4_lv_0900_as_cs" },
+    Collation { id: 282, charset: "utf8mb4", name: "utf8mb4_ro_0900_as_cs" },
+    Collation { id: 283, charset: "utf8mb4", name: "utf8mb4_sl_0900_as_cs" },
+    Collation { id: 284, charset: "utf8mb4", name: "utf8mb4_pl_0900_as_cs" },
+    Collation { id: 285, charset: "utf8mb4", name: "utf8mb4_et_0900_as_cs" },
+    Collation { id: 286, charset: "utf8mb4", name: "utf8mb4_es_0900_as_cs" },
+    Collation { id: 287, charset: "utf8mb4", name: "utf8mb4_sv_0900_as_cs" },
+    Collation { id: 288, charset: "utf8mb4", name: "utf8mb4_tr_0900_as_cs" },
+    Collation { id: 289, charset: "utf8mb4", name: "utf8mb4_cs_0900_as_cs" },
+    Collation { id: 290, charset: "utf8mb4", name: "utf8mb4_da_0900_as_cs" },
+    Collation { id: 291, charset: "utf8mb4", name: "utf8mb4_lt_0900_as_cs" },
+    Collation { id: 292, charset: "utf8mb4", name: "utf8mb4_sk_0900_as_cs" },
+    Collation { id: 293, charset: "utf8mb4", name: "utf8mb4_es_trad_0900_as_cs" },
+    Collation { id: 294, charset: "utf8mb4", name: "utf8mb4_la_0900_as_cs" },
+    Collation { id: 296, charset: "utf8mb4", name: "utf8mb4_eo_0900_as_cs" },
+    Collation { id: 297, charset: "utf8mb4", name: "utf8mb4_hu_0900_as_cs" },
+    Collation { id: 298, charset: "utf8mb4", name: "utf8mb4_hr_0900_as_cs" },
+    Collation { id: 300, charset: "utf8mb4", name: "utf8mb4_vi_0900_as_cs" },
+    Collation { id: 303, charset: "utf8mb4", name: "utf8mb4_ja_0900_as_cs" },
+    Collation { id: 304, charset: "utf8mb4", name: "utf8mb4_ja_0900_as_cs_ks" },
+    Collation { id: 305, charset: "utf8mb4", name: "utf8mb4_0900_as_ci" },
+    Collation { id: 306, charset: "utf8mb4", name: "utf8mb4_ru_0900_ai_ci" },
+    Collation { id: 307, charset: "utf8mb4", name: "utf8mb4_ru_0900_as_cs" },
+    Collation { id: 308, charset: "utf8mb4", name: "utf8mb4_zh_0900_as_cs" },
+    Collation { id: 309, charset: "utf8mb4", name: "utf8mb4_0900_bin" },
+    Collation { id: 310, charset: "utf8mb4", name: "utf8mb4_nb_0900_ai_ci" },
+    Collation { id: 311, charset: "utf8mb4", name: "utf8mb4_nb_0900_as_cs" },
+    Collation { id: 312, charset: "utf8mb4", name: "utf8mb4_nn_0900_ai_ci" },
+    Collation { id: 313, charset: "utf8mb4", name: "utf8mb4_nn_0900_as_cs" },
+    Collation { id: 314, charset: "utf8mb4", name: "utf8mb4_sr_latn_0900_ai_ci" },
+    Collation { id: 315, charset: "utf8mb4", name: "utf8mb4_sr_latn_0900_as_cs" },
+    Collation { id: 316, charset: "utf8mb4", name: "utf8mb4_bs_0900_ai_ci" },
+    Collation { id: 317, charset: "utf8mb4", name: "utf8mb4_bs_0900_as_cs" },
+    Collation { id: 318, charset: "utf8mb4", name: "utf8mb4_bg_0900_ai_ci" },
+    Collation { id: 319, charset: "utf8mb4", name: "utf8mb4_bg_0900_as_cs" },
+    Collation { id: 320, charset: "utf8mb4", name: "utf8mb4_gl_0900_ai_ci" },
+    Collation { id: 321, charset: "utf8mb4", name: "utf8mb4_gl_0900_as_cs" },
+    Collation { id: 322, charset: "utf8mb4", name: "utf8mb4_mn_cyrl_0900_ai_ci" },
+    Collation { id: 323, charset: "utf8mb4", name: "utf8mb4_mn_cyrl_0900_as_cs" },
+];
+
+static MARIADB_COLLATIONS: &[Collation] = &[
+    Collation { id: 1, charset: "big5", name: "big5_chinese_ci" },
+    Collation { id: 2, charset: "latin2", name: "latin2_czech_cs" },
+    Collation { id: 3, charset: "dec8", name: "dec8_swedish_ci" },
+    Collation { id: 4, charset: "cp850", name: "cp850_general_ci" },
+    Collation { id: 5, charset: "latin1", name: "latin1_german1_ci" },
+    Collation { id: 6, charset: "hp8", name: "hp8_english_ci" },
+    Collation { id: 7, charset: "koi8r", name: "koi8r_general_ci" },
+    Collation { id: 8, charset: "latin1", name: "latin1_swedish_ci" },
+    Collation { id: 9, charset: "latin2", name: "latin2_general_ci" },
+    Collation { id: 10, charset: "swe7", name: "swe7_swedish_ci" },
+    Collation { id: 11, charset: "ascii", name: "ascii_general_ci" },
+    Collation { id: 12, charset: "ujis", name: "ujis_japanese_ci" },
+    Collation { id: 13, charset: "sjis", name: "sjis_japanese_ci" },
+    Collation { id: 14, charset: "cp1251", name: "cp1251_bulgarian_ci" },
+    Collation { id: 15, charset: "latin1", name: "latin1_danish_ci" },
+    Collation { id: 16, charset: "hebrew", name: "hebrew_general_ci" },
+    Collation { id: 18, charset: "tis620", name: "tis620_thai_ci" },
+    Collation { id: 19, charset: "euckr", name: "euckr_korean_ci" },
+    Collation { id: 20, charset: "latin7", name: "latin7_estonian_cs" },
+    Collation { id: 21, charset: "latin2", name: "latin2_hungarian_ci" },
+    Collation { id: 22, charset: "koi8u", name: "koi8u_general_ci" },
+    Collation { id: 23, charset: "cp1251", name: "cp1251_ukrainian_ci" },
+    Collation { id: 24, charset: "gb2312", name: "gb2312_chinese_ci" },
+    Collation { id: 25, charset: "greek", name: "greek_general_ci" },
+    Collation { id: 26, charset: "cp1250", name: "cp1250_general_ci" },
+    Collation { id: 27, charset: "latin2", name: "latin2_croatian_ci" },
+    Collation { id: 28, charset: "gbk", name: "gbk_chinese_ci" },
+    Collation { id: 29, charset: "cp1257", name: "cp1257_lithuanian_ci" },
+    Collation { id: 30, charset: "latin5", name: "latin5_turkish_ci" },
+    Collation { id: 31, charset: "latin1", name: "latin1_german2_ci" },
+    Collation { id: 32, charset: "armscii8", name: "armscii8_general_ci" },
+    Collation { id: 33, charset: "utf8mb3", name: "utf8mb3_general_ci" },
+    Collation { id: 34, charset: "cp1250", name: "cp1250_czech_cs" },
+    Collation { id: 35, charset: "ucs2", name: "ucs2_general_ci" },
+    Collation { id: 36, charset: "cp866", name: "cp866_general_ci" },
+    Collation { id: 37, charset: "keybcs2", name: "keybcs2_general_ci" },
+    Collation { id: 38, charset: "macce", name: "macce_general_ci" },
+    Collation { id: 39, charset: "macroman", name: "macroman_general_ci" },
+    Collation { id: 40, charset: "cp852", name: "cp852_general_ci" },
+    Collation { id: 41, charset: "latin7", name: "latin7_general_ci" },
+    Collation { id: 42, charset: "latin7", name: "latin7_general_cs" },
+    Collation { id: 43, charset: "macce", name: "macce_bin" },
+    Collation { id: 44, charset: "cp1250", name: "cp1250_croatian_ci" },
+    Collation { id: 45, charset: "utf8mb4", name: "utf8mb4_general_ci" },
+    Collation { id: 46, charset: "utf8mb4", name: "utf8mb4_bin" },
+    Collation { id: 47, charset: "latin1", name: "latin1_bin" },
+    Collation { id: 48, charset: "latin1", name: "latin1_general_ci" },
+    Collation { id: 49, charset: "latin1", name: "latin1_general_cs" },
+    Collation { id: 50, charset: "cp1251", name: "cp1251_bin" },
+    Collation { id: 51, charset: "cp1251", name: "cp1251_general_ci" },
+    Collation { id: 52, charset: "cp1251", name: "cp1251_general_cs" },
+    Collation { id: 53, charset: "macroman", name: "macroman_bin" },
+    Collation { id: 54, charset: "utf16", name: "utf16_general_ci" },
+    Collation { id: 55, charset: "utf16", name: "utf16_bin" },
+    Collation { id: 56, charset: "utf16le", name: "utf16le_general_ci" },
+    Collation { id: 57, charset: "cp1256", name: "cp1256_general_ci" },
+    Collation { id: 58, charset: "cp1257", name: "cp1257_bin" },
+    Collation { id: 59, charset: "cp1257", name: "cp1257_general_ci" },
+    Collation { id: 60, charset: "utf32", name: "utf32_general_ci" },
+    Collation { id: 61, charset: "utf32", name: "utf32_bin" },
+    Collation { id: 62, charset: "utf16le", name: "utf16le_bin" },
+    Collation { id: 63, charset: "binary", name: "binary" },
+    Collation { id: 64, charset: "armscii8", name: "armscii8_bin" },
+    Collation { id: 65, charset: "ascii", name: "ascii_bin" },
+    Collation { id: 66, charset: "cp1250", name: "cp1250_bin" },
+    Collation { id: 67, charset: "cp1256", name: "cp1256_bin" },
+    Collation { id: 68, charset: "cp866", name: "cp866_bin" },
+    Collation { id: 69, charset: "dec8", name: "dec8_bin" },
+    Collation { id: 70, charset: "greek", name: "greek_bin" },
+    Collation { id: 71, charset: "hebrew", name: "hebrew_bin" },
+    Collation { id: 72, charset: "hp8", name: "hp8_bin" },
+    Collation { id: 73, charset: "keybcs2", name: "keybcs2_bin" },
+    Collation { id: 74, charset: "koi8r", name: "koi8r_bin" },
+    Collation { id: 75, charset: "koi8u", name: "koi8u_bin" },
+    Collation { id: 77, charset: "latin2", name: "latin2_bin" },
+    Collation { id: 78, charset: "latin5", name: "latin5_bin" },
+    Collation { id: 79, charset: "latin7", name: "latin7_bin" },
+    Collation { id: 80, charset: "cp850", name: "cp850_bin" },
+    Collation { id: 81, charset: "cp852", name: "cp852_bin" },
+    Collation { id: 82, charset: "swe7", name: "swe7_bin" },
+    Collation { id: 83, charset: "utf8mb3", name: "utf8mb3_bin" },
+    Collation { id: 84, charset: "big5", name: "big5_bin" },
+    Collation { id: 85, charset: "euckr", name: "euckr_bin" },
+    Collation { id: 86, charset: "gb2312", name: "gb2312_bin" },
+    Collation { id: 87, charset: "gbk", name: "gbk_bin" },
+    Collation { id: 88, charset: "sjis", name: "sjis_bin" },
+    Collation { id: 89, charset: "tis620", name: "tis620_bin" },
+    Collation { id: 90, charset: "ucs2", name: "ucs2_bin" },
+    Collation { id: 91, charset: "ujis", name: "ujis_bin" },
+    Collation { id: 92, charset: "geostd8", name: "geostd8_general_ci" },
+    Collation { id: 93, charset: "geostd8", name: "geostd8_bin" },
+    Collation { id: 94, charset: "latin1", name: "latin1_spanish_ci" },
+    Collation { id: 95, charset: "cp932", name: "cp932_japanese_ci" },
+    Collation { id: 96, charset: "cp932", name: "cp932_bin" },
+    Collation { id: 97, charset: "eucjpms", name: "eucjpms_japanese_ci" },
+    Collation { id: 98, charset: "eucjpms", name: "eucjpms_bin" },
+    Collation { id: 99, charset: "cp1250", name: "cp1250_polish_ci" },
+    Collation { id: 101, charset: "utf16", name: "utf16_unicode_ci" },
+    Collation { id: 102, charset: "utf16", name: "utf16_icelandic_ci" },
+    Collation { id: 103, charset: "utf16", name: "utf16_latvian_ci" },
+    Collation { id: 104, charset: "utf16", name: "utf16_romanian_ci" },
+    Collation { id: 105, charset: "utf16", name: "utf16_slovenian_ci" },
+    Collation { id: 106, charset: "utf16", name: "utf16_polish_ci" },
+    Collation { id: 107, charset: "utf16", name: "utf16_estonian_ci" },
+    Collation { id: 108, charset: "utf16", name: "utf16_spanish_ci" },
+    Collation { id: 109, charset: "utf16", name: "utf16_swedish_ci" },
+    Collation { id: 110, charset: "utf16", name: "utf16_turkish_ci" },
+    Collation { id: 111, charset: "utf16", name: "utf16_czech_ci" },
+    Collation { id: 112, charset: "utf16", name: "utf16_danish_ci" },
+    Collation { id: 113, charset: "utf16", name: "utf16_lithuanian_ci" },
+    Collation { id: 114, charset: "utf16", name: "utf16_slovak_ci" },
+    Collation { id: 115, charset: "utf16", name: "utf16_spanish2_ci" },
+    Collation { id: 116, charset: "utf16", name: "utf16_roman_ci" },
+    Collation { id: 117, charset: "utf16", name: "utf16_persian_ci" },
+    Collation { id: 118, charset: "utf16", name: "utf16_esperanto_ci" },
+    Collation { id: 119, charset: "utf16", name: "utf16_hungarian_ci" },
+    Collation { id: 120, charset: "utf16", name: "utf16_sinhala_ci" },
+    Collation { id: 121, charset: "utf16", name: "utf16_german2_ci" },
+    Collation { id: 122, charset: "utf16", name: "utf16_croatian_ci" },
+    Collation { id: 123, charset: "utf16", name: "utf16_unicode_520_ci" },
+    Collation { id: 124, charset: "utf16", name: "utf16_vietnamese_ci" },
+    Collation { id: 128, charset: "ucs2", name: "ucs2_unicode_ci" },
+    Collation { id: 129, charset: "ucs2", name: "ucs2_icelandic_ci" },
+    Collation { id: 130, charset: "ucs2", name: "ucs2_latvian_ci" },
+    Collation { id: 131, charset: "ucs2", name: "ucs2_romanian_ci" },
+    Collation { id: 132, charset: "ucs2", name: "ucs2_slovenian_ci" },
+    Collation { id: 133, charset: "ucs2", name: "ucs2_polish_ci" },
+    Collation { id: 134, charset: "ucs2", name: "ucs2_estonian_ci" },
+    Collation { id: 135, charset: "ucs2", name: "ucs2_spanish_ci" },
+    Collation { id: 136, charset: "ucs2", name: "ucs2_swedish_ci" },
+    Collation { id: 137, charset: "ucs2", name: "ucs2_turkish_ci" },
+    Collation { id: 138, charset: "ucs2", name: "ucs2_czech_ci" },
+    Collation { id: 139, charset: "ucs2", name: "ucs2_danish_ci" },
+    Collation { id: 140, charset: "ucs2", name: "ucs2_lithuanian_ci" },
+    Collation { id: 141, charset: "ucs2", name: "ucs2_slovak_ci" },
+    Collation { id: 142, charset: "ucs2", name: "ucs2_spanish2_ci" },
+    Collation { id: 143, charset: "ucs2", name: "ucs2_roman_ci" },
+    Collation { id: 144, charset: "ucs2", name: "ucs2_persian_ci" },
+    Collation { id: 145, charset: "ucs2", name: "ucs2_esperanto_ci" },
+    Collation { id: 146, charset: "ucs2", name: "ucs2_hungarian_ci" },
+    Collation { id: 147, charset: "ucs2", name: "ucs2_sinhala_ci" },
+    Collation { id: 148, charset: "ucs2", name: "ucs2_german2_ci" },
+    Collation { id: 149, charset: "ucs2", name: "ucs2_croatian_ci" },
+    Collation { id: 150, charset: "ucs2", name: "ucs2_unicode_520_ci" },
+    Collation { id: 151, charset: "ucs2", name: "ucs2_vietnamese_ci" },
+    Collation { id: 159, charset: "ucs2", name: "ucs2_general_mysql500_ci" },
+    Collation { id: 160, charset: "utf32", name: "utf32_unicode_ci" },
+    Collation { id: 161, charset: "utf32", name: "utf32_icelandic_ci" },
+    Collation { id: 162, charset: "utf32", name: "utf32_latvian_ci" },
+    Collation { id: 163, charset: "utf32", name: "utf32_romanian_ci" },
+    Collation { id: 164, charset: "utf32", name: "utf32_slovenian_ci" },
+    Collation { id: 165, charset: "utf32", name: "utf32_polish_ci" },
+    Collation { id: 166, charset: "utf32", name: "utf32_estonian_ci" },
+    Collation { id: 167, charset: "utf32", name: "utf32_spanish_ci" },
+    Collation { id: 168, charset: "utf32", name: "utf32_swedish_ci" },
+    Collation { id: 169, charset: "utf32", name: "utf32_turkish_ci" },
+    Collation { id: 170, charset: "utf32", name: "utf32_czech_ci" },
+    Collation { id: 171, charset: "utf32", name: "utf32_danish_ci" },
+    Collation { id: 172, charset: "utf32", name: "utf32_lithuanian_ci" },
+    Collation { id: 173, charset: "utf32", name: "utf32_slovak_ci" },
+    Collation { id: 174, charset: "utf32", name: "utf32_spanish2_ci" },
+    Collation { id: 175, charset: "utf32", name: "utf32_roman_ci" },
+    Collation { id: 176, charset: "utf32", name: "utf32_persian_ci" },
+    Collation { id: 177, charset: "utf32", name: "utf32_esperanto_ci" },
+    Collation { id: 178, charset: "utf32", name: "utf32_hungarian_ci" },
+    Collation { id: 179, charset: "utf32", name: "utf32_sinhala_ci" },
+    Collation { id: 180, charset: "utf32", name: "utf32_german2_ci" },
+    Collation { id: 181, charset: "utf32", name: "utf32_croatian_ci" },
+    Collation { id: 182, charset: "utf32", name: "utf32_unicode_520_ci" },
+    Collation { id: 183, charset: "utf32", name: "utf32_vietnamese_ci" },
+    Collation { id: 192, charset: "utf8mb3", name: "utf8mb3_unicode_ci" },
+    Collation { id: 193, charset: "utf8mb3", name: "utf8mb3_icelandic_ci" },
+    Collation { id: 194, charset: "utf8mb3", name: "utf8mb3_latvian_ci" },
+    Collation { id: 195, charset: "utf8mb3", name: "utf8mb3_romanian_ci" },
+    Collation { id: 196, charset: "utf8mb3", name: "utf8mb3_slovenian_ci" },
+    Collation { id: 197, charset: "utf8mb3", name: "utf8mb3_polish_ci" },
+    Collation { id: 198, charset: "utf8mb3", name: "utf8mb3_estonian_ci" },
+    Collation { id: 199, charset: "utf8mb3", name: "utf8mb3_spanish_ci" },
+    Collation { id: 200, charset: "utf8mb3", name: "utf8mb3_swedish_ci" },
+    Collation { id: 201, charset: "utf8mb3", name: "utf8mb3_turkish_ci" },
+    Collation { id: 202, charset: "utf8mb3", name: "utf8mb3_czech_ci" },
+    Collation { id: 203, charset: "utf8mb3", name: "utf8mb3_danish_ci" },
+    Collation { id: 204, charset: "utf8mb3", name: "utf8mb3_lithuanian_ci" },
+    Collation { id: 205, charset: "utf8mb3", name: "utf8mb3_slovak_ci" },
+    Collation { id: 206, charset: "utf8mb3", name: "utf8mb3_spanish2_ci" },
+    Collation { id: 207, charset: "utf8mb3", name: "utf8mb3_roman_ci" },
+    Collation { id: 208, charset: "utf8mb3", name: "utf8mb3_persian_ci" },
+    Collation { id: 209, charset: "utf8mb3", name: "utf8mb3_esperanto_ci" },
+    Collation { id: 210, charset: "utf8mb3", name: "utf8mb3_hungarian_ci" },
+    Collation { id: 211, charset: "utf8mb3", name: "utf8mb3_sinhala_ci" },
+    Collation { id: 212, charset: "utf8mb3", name: "utf8mb3_german2_ci" },
+    Collation { id: 213, charset: "utf8mb3", name: "utf8mb3_croatian_ci" },
+    Collation { id: 214, charset: "utf8mb3", name: "utf8mb3_unicode_520_ci" },
+    Collation { id: 215, charset: "utf8mb3", name: "utf8mb3_vietnamese_ci" },
+    Collation { id: 223, charset: "utf8mb3", name: "utf8mb3_general_mysql500_ci" },
+    Collation { id: 224, charset: "utf8mb4", name: "utf8mb4_unicode_ci" },
+    Collation { id: 225, charset: "utf8mb4", name: "utf8mb4_icelandic_ci" },
+    Collation { id: 226, charset: "utf8mb4", name: "utf8mb4_latvian_ci" },
+    Collation { id: 227, charset: "utf8mb4", name: "utf8mb4_romanian_ci" },
+    Collation { id: 228, charset: "utf8mb4", name: "utf8mb4_slovenian_ci" },
+    Collation { id: 229, charset: "utf8mb4", name: "utf8mb4_polish_ci" },
+    Collation { id: 230, charset: "utf8mb4", name: "utf8mb4_estonian_ci" },
+    Collation { id: 231, charset: "utf8mb4", name: "utf8mb4_spanish_ci" },
+    Collation { id: 232, charset: "utf8mb4", name: "utf8mb4_swedish_ci" },
+    Collation { id: 233, charset: "utf8mb4", name: "utf8mb4_turkish_ci" },
+    Collation { id: 234, charset: "utf8mb4", name: "utf8mb4_czech_ci" },
+    Collation { id: 235, charset: "utf8mb4", name: "utf8mb4_danish_ci" },
+    Collation { id: 236, charset: "utf8mb4", name: "utf8mb4_lithuanian_ci" },
+    Collation { id: 237, charset: "utf8mb4", name: "utf8mb4_slovak_ci" },
+    Collation { id: 238, charset: "utf8mb4", name: "utf8mb4_spanish2_ci" },
+    Collation { id: 239, charset: "utf8mb4", name: "utf8mb4_roman_ci" },
+    Collation { id: 240, charset: "utf8mb4", name: "utf8mb4_persian_ci" },
+    Collation { id: 241, charset: "utf8mb4", name: "utf8mb4_esperanto_ci" },
+    Collation { id: 242, charset: "utf8mb4", name: "utf8mb4_hungarian_ci" },
+    Collation { id: 243, charset: "utf8mb4", name: "utf8mb4_sinhala_ci" },
+    Collation { id: 244, charset: "utf8mb4", name: "utf8mb4_german2_ci" },
+    Collation { id: 245, charset: "utf8mb4", name: "utf8mb4_croatian_ci" },
+    Collation { id: 246, charset: "utf8mb4", name: "utf8mb4_unicode_520_ci" },
+    Collation { id: 247, charset: "utf8mb4", name: "utf8mb4_vietnamese_ci" },
+    Collation { id: 576, charset: "utf8mb3", name: "utf8mb3_croatian_mysql561_ci" },
+    Collation { id: 577, charset: "utf8mb3", name: "utf8mb3_myanmar_ci" },
+    Collation { id: 578, charset: "utf8mb3", name: "utf8mb3_thai_520_w2" },
+    Collation { id: 608, charset: "utf8mb4", name: "utf8mb4_croatian_mysql561_ci" },
+    Collation { id: 609, charset: "utf8mb4", name: "utf8mb4_myanmar_ci" },
+    Collation { id: 610, charset: "utf8mb4", name: "utf8mb4_thai_520_w2" },
+    Collation { id: 640, charset: "ucs2", name: "ucs2_croatian_mysql561_ci" },
+    Collation { id: 641, charset: "ucs2", name: "ucs2_myanmar_ci" },
+    Collation { id: 642, charset: "ucs2", name: "ucs2_thai_520_w2" },
+    Collation { id: 672, charset: "utf16", name: "utf16_croatian_mysql561_ci" },
+    Collation { id: 673, charset: "utf16", name: "utf16_myanmar_ci" },
+    Collation { id: 674, charset: "utf16", name: "utf16_thai_520_w2" },
+    Collation { id: 736, charset: "utf32", name: "utf32_croatian_mysql561_ci" },
+    Collation { id: 737, charset: "utf32", name: "utf32_myanmar_ci" },
+    Collation { id: 738, charset: "utf32", name: "utf32_thai_520_w2" },
+    Collation { id: 1025, charset: "big5", name: "big5_chinese_nopad_ci" },
+    Collation { id: 1027, charset: "dec8", name: "dec8_swedish_nopad_ci" },
+    Collation { id: 1028, charset: "cp850", name: "cp850_general_nopad_ci" },
+    Collation { id: 1030, charset: "hp8", name: "hp8_english_nopad_ci" },
+    Collation { id: 1031, charset: "koi8r", name: "koi8r_general_nopad_ci" },
+    Collation { id: 1032, charset: "latin1", name: "latin1_swedish_nopad_ci" },
+    Collation { id: 1033, charset: "latin2", name: "latin2_general_nopad_ci" },
+    Collation { id: 1034, charset: "swe7", name: "swe7_swedish_nopad_ci" },
+    Collation { id: 1035, charset: "ascii", name: "ascii_general_nopad_ci" },
+    Collation { id: 1036, charset: "ujis", name: "ujis_japanese_nopad_ci" },
+    Collation { id: 1037, charset: "sjis", name: "sjis_japanese_nopad_ci" },
+    Collation { id: 1040, charset: "hebrew", name: "hebrew_general_nopad_ci" },
+    Collation { id: 1042, charset: "tis620", name: "tis620_thai_nopad_ci" },
+    Collation { id: 1043, charset: "euckr", name: "euckr_korean_nopad_ci" },
+    Collation { id: 1046, charset: "koi8u", name: "koi8u_general_nopad_ci" },
+    Collation { id: 1048, charset: "gb2312", name: "gb2312_chinese_nopad_ci" },
+    Collation { id: 1049, charset: "greek", name: "greek_general_nopad_ci" },
+    Collation { id: 1050, charset: "cp1250", name: "cp1250_general_nopad_ci" },
+    Collation { id: 1052, charset: "gbk", name: "gbk_chinese_nopad_ci" },
+    Collation { id: 1054, charset: "latin5", name: "latin5_turkish_nopad_ci" },
+    Collation { id: 1056, charset: "armscii8", name: "armscii8_general_nopad_ci" },
+    Collation { id: 1057, charset: "utf8mb3", name: "utf8mb3_general_nopad_ci" },
+    Collation { id: 1059, charset: "ucs2", name: "ucs2_general_nopad_ci" },
+    Collation { id: 1060, charset: "cp866", name: "cp866_general_nopad_ci" },
+    Collation { id: 1061, charset: "keybcs2", name: "keybcs2_general_nopad_ci" },
+    Collation { id: 1062, charset: "macce", name: "macce_general_nopad_ci" },
+    Collation { id: 1063, charset: "macroman", name: "macroman_general_nopad_ci" },
+    Collation { id: 1064, charset: "cp852", name: "cp852_general_nopad_ci" },
+    Collation { id: 1065, charset: "latin7", name: "latin7_general_nopad_ci" },
+    Collation { id: 1067, charset: "macce", name: "macce_nopad_bin" },
+    Collation { id: 1069, charset: "utf8mb4", name: "utf8mb4_general_nopad_ci" },
+    Collation { id: 1070, charset: "utf8mb4", name: "utf8mb4_nopad_bin" },
+    Collation { id: 1071, charset: "latin1", name: "latin1_nopad_bin" },
+    Collation { id: 1074, charset: "cp1251", name: "cp1251_nopad_bin" },
+    Collation { id: 1075, charset: "cp1251", name: "cp1251_general_nopad_ci" },
+    Collation { id: 1077, charset: "macroman", name: "macroman_nopad_bin" },
+    Collation { id: 1078, charset: "utf16", name: "utf16_general_nopad_ci" },
+    Collation { id: 1079, charset: "utf16", name: "utf16_nopad_bin" },
+    Collation { id: 1080, charset: "utf16le", name: "utf16le_general_nopad_ci" },
+    Collation { id: 1081, charset: "cp1256", name: "cp1256_general_nopad_ci" },
+    Collation { id: 1082, charset: "cp1257", name: "cp1257_nopad_bin" },
+    Collation { id: 1083, charset: "cp1257", name: "cp1257_general_nopad_ci" },
+    Collation { id: 1084, charset: "utf32", name: "utf32_general_nopad_ci" },
+    Collation { id: 1085, charset: "utf32", name: "utf32_nopad_bin" },
+    Collation { id: 1086, charset: "utf16le", name: "utf16le_nopad_bin" },
+    Collation { id: 1088, charset: "armscii8", name: "armscii8_nopad_bin" },
+    Collation { id: 1089, charset: "ascii", name: "ascii_nopad_bin" },
+    Collation { id: 1090, charset: "cp1250", name: "cp1250_nopad_bin" },
+    Collation { id: 1091, charset: "cp1256", name: "cp1256_nopad_bin" },
+    Collation { id: 1092, charset: "cp866", name: "cp866_nopad_bin" },
+    Collation { id: 1093, charset: "dec8", name: "dec8_nopad_bin" },
+    Collation { id: 1094, charset: "greek", name: "greek_nopad_bin" },
+    Collation { id: 1095, charset: "hebrew", name: "hebrew_nopad_bin" },
+    Collation { id: 1096, charset: "hp8", name: "hp8_nopad_bin" },
+    Collation { id: 1097, charset: "keybcs2", name: "keybcs2_nopad_bin" },
+    Collation { id: 1098, charset: "koi8r", name: "koi8r_nopad_bin" },
+    Collation { id: 1099, charset: "koi8u", name: "koi8u_nopad_bin" },
+    Collation { id: 1101, charset: "latin2", name: "latin2_nopad_bin" },
+    Collation { id: 1102, charset: "latin5", name: "latin5_nopad_bin" },
+    Collation { id: 1103, charset: "latin7", name: "latin7_nopad_bin" },
+    Collation { id: 1104, charset: "cp850", name: "cp850_nopad_bin" },
+    Collation { id: 1105, charset: "cp852", name: "cp852_nopad_bin" },
+    Collation { id: 1106, charset: "swe7", name: "swe7_nopad_bin" },
+    Collation { id: 1107, charset: "utf8mb3", name: "utf8mb3_nopad_bin" },
+    Collation { id: 1108, charset: "big5", name: "big5_nopad_bin" },
+    Collation { id: 1109, charset: "euckr", name: "euckr_nopad_bin" },
+    Collation { id: 1110, charset: "gb2312", name: "gb2312_nopad_bin" },
+    Collation { id: 1111, charset: "gbk", name: "gbk_nopad_bin" },
+    Collation { id: 1112, charset: "sjis", name: "sjis_nopad_bin" },
+    Collation { id: 1113, charset: "tis620", name: "tis620_nopad_bin" },
+    Collation { id: 1114, charset: "ucs2", name: "ucs2_nopad_bin" },
+    Collation { id: 1115, charset: "ujis", name: "ujis_nopad_bin" },
+    Collation { id: 1116, charset: "geostd8", name: "geostd8_general_nopad_ci" },
+    Collation { id: 1117, charset: "geostd8", name: "geostd8_nopad_bin" },
+    Collation { id: 1119, charset: "cp932", name: "cp932_japanese_nopad_ci" },
+    Collation { id: 1120, charset: "cp932", name: "cp932_nopad_bin" },
+    Collation { id: 1121, charset: "eucjpms", name: "eucjpms_japanese_nopad_ci" },
+    Collation { id: 1122, charset: "eucjpms", name: "eucjpms_nopad_bin" },
+    Collation { id: 1125, charset: "utf16", name: "utf16_unicode_nopad_ci" },
+    Collation { id: 1147, charset: "utf16", name: "utf16_unicode_520_nopad_ci" },
+    Collation { id: 1152, charset: "ucs2", name: "ucs2_unicode_nopad_ci" },
+    Collation { id: 1174, charset: "ucs2", name: "ucs2_unicode_520_nopad_ci" },
+    Collation { id: 1184, charset: "utf32", name: "utf32_unicode_nopad_ci" },
+    Collation { id: 1206, charset: "utf32", name: "utf32_unicode_520_nopad_ci" },
+    Collation { id: 1216, charset: "utf8mb3", name: "utf8mb3_unicode_nopad_ci" },
+    Collation { id: 1238, charset: "utf8mb3", name: "utf8mb3_unicode_520_nopad_ci" },
+    Collation { id: 1248, charset: "utf8mb4", name: "utf8mb4_unicode_nopad_ci" },
+    Collation { id: 1270, charset: "utf8mb4", name: "utf8mb4_unicode_520_nopad_ci" },
+];
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+    use connection::ConnectOptions;
+    use mysql_set_character_set;
+
+    fn assert_sorted_and_unique(table: &[Collation]) {
+        for pair in table.windows(2) {
+            assert!(pair[0].id < pair[1].id, "{:?} before {:?}", pair[0], pair[1]);
+        }
+    }
+
+    fn names(collation: Option<Collation>) -> Option<(&'static str, &'static str)> {
+        collation.map(|c| (c.charset, c.name))
+    }
+
+    #[test]
+    fn tables_are_sorted_and_unique() {
+        assert_sorted_and_unique(MYSQL_COLLATIONS);
+        assert_sorted_and_unique(MARIADB_COLLATIONS);
+    }
+
+    #[test]
+    fn shared_ids() {
+        for lookup in &[mysql_collation, mariadb_collation] {
+            assert_eq!(names(lookup(63)), Some(("binary", "binary")));
+            assert!(lookup(63).unwrap().is_binary());
+            assert_eq!(names(lookup(45)), Some(("utf8mb4", "utf8mb4_general_ci")));
+            assert!(!lookup(45).unwrap().is_binary());
+            assert_eq!(names(lookup(8)), Some(("latin1", "latin1_swedish_ci")));
+            assert_eq!(lookup(0), None);
+        }
+    }
+
+    #[test]
+    fn server_specific_ids() {
+        assert_eq!(names(mysql_collation(255)), Some(("utf8mb4", "utf8mb4_0900_ai_ci")));
+        assert_eq!(mariadb_collation(255), None);
+        assert_eq!(names(mariadb_collation(1248)),
+                   Some(("utf8mb4", "utf8mb4_unicode_nopad_ci")));
+        assert_eq!(mysql_collation(1248), None);
+    }
+
+    /// Tests that need a server are ignored; run them with
+    /// `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn connection_charset() {
+        let conn = Connection::connect(&ConnectOptions::new()).expect("cannot reach a server");
+        let name = CString::new("utf8mb4").unwrap();
+        assert_eq!(unsafe { mysql_set_character_set(conn.as_ptr(), name.as_ptr()) }, 0);
+        let info = conn.charset();
+        assert_eq!(info.name, "utf8mb4");
+        assert_eq!((info.mbminlen, info.mbmaxlen), (1, 4));
+        let collation = mysql_collation(info.number)
+            .or_else(|| mariadb_collation(info.number))
+            .expect("unknown collation id");
+        assert_eq!(collation.name, info.collation);
+    }
+}
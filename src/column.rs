@@ -0,0 +1,257 @@
+use std::borrow::Cow;
+use std::os::raw::{c_char, c_uint};
+use std::ptr;
+use std::slice;
+
+use error::{MysqlError, Result};
+use result::RawResult;
+use {BINARY_CHARSET_NR, BINARY_FLAG, ENUM_FLAG, MYSQL_FIELD, MYSQL_STMT, NOT_NULL_FLAG,
+     PRI_KEY_FLAG, SET_FLAG, UNSIGNED_FLAG, enum_field_types, mysql_stmt_errno,
+     mysql_stmt_result_metadata};
+
+/// A typed view of the `MYSQL_FIELD` describing one column of a result set.
+#[repr(transparent)]
+pub struct Column(MYSQL_FIELD);
+
+impl Column {
+    pub(crate) unsafe fn slice<'a>(fields: *const MYSQL_FIELD, len: usize) -> &'a [Column] {
+        if len == 0 {
+            &[]
+        } else {
+            slice::from_raw_parts(fields as *const Column, len)
+        }
+    }
+
+    pub fn as_raw(&self) -> &MYSQL_FIELD {
+        &self.0
+    }
+
+    /// The column name or alias, as it appears in the result set.
+    pub fn name(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.name_bytes())
+    }
+
+    pub fn name_bytes(&self) -> &[u8] {
+        unsafe { bytes(self.0.name, self.0.name_length) }
+    }
+
+    /// The column name before any alias was applied.
+    pub fn org_name(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.org_name_bytes())
+    }
+
+    pub fn org_name_bytes(&self) -> &[u8] {
+        unsafe { bytes(self.0.org_name, self.0.org_name_length) }
+    }
+
+    /// The table name or alias the column belongs to.
+    pub fn table(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.table_bytes())
+    }
+
+    pub fn table_bytes(&self) -> &[u8] {
+        unsafe { bytes(self.0.table, self.0.table_length) }
+    }
+
+    /// The table name before any alias was applied.
+    pub fn org_table(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.org_table_bytes())
+    }
+
+    pub fn org_table_bytes(&self) -> &[u8] {
+        unsafe { bytes(self.0.org_table, self.0.org_table_length) }
+    }
+
+    pub fn db(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.db_bytes())
+    }
+
+    pub fn db_bytes(&self) -> &[u8] {
+        unsafe { bytes(self.0.db, self.0.db_length) }
+    }
+
+    /// The catalog name, which is always `def`.
+    pub fn catalog(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.catalog_bytes())
+    }
+
+    pub fn catalog_bytes(&self) -> &[u8] {
+        unsafe { bytes(self.0.catalog, self.0.catalog_length) }
+    }
+
+    pub fn column_type(&self) -> enum_field_types {
+        self.0.type_
+    }
+
+    /// The raw `*_FLAG` bits of the column.
+    pub fn flags(&self) -> c_uint {
+        self.0.flags
+    }
+
+    pub fn is_nullable(&self) -> bool {
+        self.0.flags & NOT_NULL_FLAG == 0
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        self.0.flags & UNSIGNED_FLAG != 0
+    }
+
+    pub fn is_primary_key(&self) -> bool {
+        self.0.flags & PRI_KEY_FLAG != 0
+    }
+
+    /// Whether the column holds binary data rather than text.
+    ///
+    /// String columns are binary when their character set is `binary` (63).
+    /// `BINARY_FLAG` alone is not enough, since it is also set for text
+    /// columns using a `_bin` collation.
+    pub fn is_binary(&self) -> bool {
+        self.0.charsetnr == BINARY_CHARSET_NR
+    }
+
+    /// Whether the `BINARY_FLAG` bit is set for this column.
+    pub fn has_binary_flag(&self) -> bool {
+        self.0.flags & BINARY_FLAG != 0
+    }
+
+    /// Whether the column is an `ENUM`, which is reported as a string column
+    /// with `ENUM_FLAG` set.
+    pub fn is_enum(&self) -> bool {
+        self.0.flags & ENUM_FLAG != 0
+    }
+
+    /// Whether the column is a `SET`, which is reported as a string column
+    /// with `SET_FLAG` set.
+    pub fn is_set(&self) -> bool {
+        self.0.flags & SET_FLAG != 0
+    }
+
+    /// The collation id of the column, as used by `character_set_results`.
+    pub fn charset_id(&self) -> u32 {
+        self.0.charsetnr
+    }
+
+    /// The number of decimals for numeric and fractional time columns.
+    pub fn decimals(&self) -> u32 {
+        self.0.decimals
+    }
+
+    /// The width of the column as declared in the table definition.
+    pub fn display_length(&self) -> u64 {
+        self.0.length as u64
+    }
+
+    /// The length of the longest value in a buffered result set.
+    ///
+    /// This is only computed for results read with `mysql_store_result`, or
+    /// for prepared statements with `STMT_ATTR_UPDATE_MAX_LENGTH` enabled.
+    pub fn max_length(&self) -> u64 {
+        self.0.max_length as u64
+    }
+}
+
+#[cfg(test)]
+impl Column {
+    /// An unnamed column, for unit tests of decoding.
+    pub(crate) fn for_test(column_type: enum_field_types, flags: c_uint, charset_id: u32) -> Self {
+        let mut field: MYSQL_FIELD = unsafe { ::std::mem::zeroed() };
+        field.type_ = column_type;
+        field.flags = flags;
+        field.charsetnr = charset_id;
+        Column(field)
+    }
+
+    pub(crate) fn with_length(mut self, length: u64, decimals: u32) -> Self {
+        self.0.length = length as _;
+        self.0.decimals = decimals;
+        self
+    }
+}
+
+/// Column metadata copied out of the result it was read from, so that it
+/// outlives the result and can be sent to other threads.
+pub struct ColumnSet {
+    fields: Vec<MYSQL_FIELD>,
+    /// The names the copied fields point into.
+    _names: Vec<Box<[u8]>>,
+}
+
+// The fields only point into `_names`, which is never modified.
+unsafe impl Send for ColumnSet {}
+unsafe impl Sync for ColumnSet {}
+
+impl ColumnSet {
+    pub fn new(columns: &[Column]) -> Self {
+        let mut names = Vec::with_capacity(columns.len() * 6);
+        let fields = columns.iter()
+            .map(|column| {
+                let mut field = column.0;
+                unsafe {
+                    copy_name(&mut names, &mut field.name, field.name_length);
+                    copy_name(&mut names, &mut field.org_name, field.org_name_length);
+                    copy_name(&mut names, &mut field.table, field.table_length);
+                    copy_name(&mut names, &mut field.org_table, field.org_table_length);
+                    copy_name(&mut names, &mut field.db, field.db_length);
+                    copy_name(&mut names, &mut field.catalog, field.catalog_length);
+                }
+                field.def = ptr::null_mut();
+                field.def_length = 0;
+                field.extension = ptr::null_mut();
+                field
+            })
+            .collect();
+        ColumnSet {
+            fields,
+            _names: names,
+        }
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        unsafe { Column::slice(self.fields.as_ptr(), self.fields.len()) }
+    }
+}
+
+/// Points `name` at an owned copy of the string it points to.
+unsafe fn copy_name(names: &mut Vec<Box<[u8]>>, name: &mut *mut c_char, len: c_uint) {
+    let mut owned: Box<[u8]> = bytes(*name, len).into();
+    *name = owned.as_mut_ptr() as *mut c_char;
+    names.push(owned);
+}
+
+unsafe fn bytes<'a>(ptr: *const c_char, len: c_uint) -> &'a [u8] {
+    if ptr.is_null() || len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(ptr as *const u8, len as usize)
+    }
+}
+
+/// The result set metadata of a prepared statement, as returned by
+/// `mysql_stmt_result_metadata`.
+pub struct ResultMetadata {
+    raw: RawResult,
+}
+
+impl ResultMetadata {
+    /// Fetches the result set metadata of a prepared statement.
+    ///
+    /// Returns `Ok(None)` if the statement does not produce a result set.
+    ///
+    /// # Safety
+    ///
+    /// `stmt` must be a valid statement handle that has been prepared.
+    pub unsafe fn from_statement(stmt: *mut MYSQL_STMT) -> Result<Option<Self>> {
+        let res = mysql_stmt_result_metadata(stmt);
+        if !res.is_null() {
+            Ok(Some(ResultMetadata { raw: RawResult::new(res) }))
+        } else if mysql_stmt_errno(stmt) == 0 {
+            Ok(None)
+        } else {
+            Err(MysqlError::from_statement(stmt).into())
+        }
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        self.raw.columns()
+    }
+}
@@ -0,0 +1,120 @@
+//! Chunked reading of large column values with `mysql_stmt_fetch_column`.
+
+use std::cmp;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::{c_uint, c_ulong, c_void};
+
+use binder::{BoundResult, fixed_size};
+use error::MysqlError;
+use {MYSQL_BIND, MYSQL_STMT, enum_field_types, my_bool, mysql_stmt_fetch_column};
+
+/// Reads the value of one column of the current row in chunks.
+///
+/// Each `read` fetches the requested range with `mysql_stmt_fetch_column`
+/// at the current offset, so only the caller's buffer is held in memory.
+pub struct ColumnReader<'a> {
+    stmt: *mut MYSQL_STMT,
+    column: c_uint,
+    buffer_type: enum_field_types,
+    len: u64,
+    pos: u64,
+    _result: PhantomData<&'a mut ()>,
+}
+
+impl<'stmt, 'conn> BoundResult<'stmt, 'conn> {
+    /// Returns a reader over column `idx` of the row last returned by
+    /// `next_row`, or `None` if the value is `NULL` or the column has a
+    /// fixed-size type such as an integer or `MYSQL_TIME`.
+    ///
+    /// Columns registered with `ResultBinder::stream_column` are only
+    /// available this way. Fixed-size values are always written whole,
+    /// ignoring the offset and length, so they cannot be read in chunks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn column_reader(&mut self, idx: usize) -> Option<ColumnReader<'_>> {
+        let buffer = &self.buffers[idx];
+        if buffer.is_null != 0 || fixed_size(buffer.buffer_type).is_some() {
+            return None;
+        }
+        Some(ColumnReader {
+            stmt: self.stmt.as_ptr(),
+            column: idx as c_uint,
+            buffer_type: buffer.buffer_type,
+            len: buffer.length as u64,
+            pos: 0,
+            _result: PhantomData,
+        })
+    }
+}
+
+impl<'a> ColumnReader<'a> {
+    /// The total length of the value in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<'a> Read for ColumnReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        let n = cmp::min(remaining, buf.len() as u64) as usize;
+        if n == 0 {
+            return Ok(0);
+        }
+        let mut length: c_ulong = 0;
+        let mut is_null: my_bool = 0;
+        let mut error: my_bool = 0;
+        let mut bind: MYSQL_BIND = unsafe { mem::zeroed() };
+        bind.buffer_type = self.buffer_type;
+        bind.buffer = buf.as_mut_ptr() as *mut c_void;
+        bind.buffer_length = n as c_ulong;
+        bind.length = &mut length;
+        bind.is_null = &mut is_null;
+        bind.error = &mut error;
+        let rc = unsafe {
+            mysql_stmt_fetch_column(self.stmt, &mut bind, self.column, self.pos as c_ulong)
+        };
+        if rc != 0 {
+            let err = unsafe { MysqlError::from_statement(self.stmt) };
+            return Err(io::Error::other(err));
+        }
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<'a> Seek for ColumnReader<'a> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => offset_by(self.len, offset),
+            SeekFrom::Current(offset) => offset_by(self.pos, offset),
+        };
+        match new_pos {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => {
+                Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                   "invalid seek to a negative or overflowing position"))
+            }
+        }
+    }
+}
+
+fn offset_by(base: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        base.checked_add(offset as u64)
+    } else {
+        base.checked_sub(offset.unsigned_abs())
+    }
+}
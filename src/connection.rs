@@ -0,0 +1,397 @@
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::{c_char, c_uint, c_ulong, c_void};
+use std::ptr::{self, NonNull};
+use std::sync::{Arc, Once};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use error::{Error, MysqlError, Result};
+use handle_info;
+use result::{BufferedResult, StreamingResult};
+use {MYSQL, SERVER_STATUS_IN_TRANS, mysql_affected_rows, mysql_change_user, mysql_close,
+     mysql_errno, mysql_field_count, mysql_get_option, mysql_init, mysql_insert_id,
+     mysql_more_results, mysql_next_result, mysql_option, mysql_ping, mysql_real_connect,
+     mysql_real_query, mysql_reset_connection, mysql_server_init, mysql_store_result,
+     mysql_thread_id, mysql_use_result, mysql_warning_count};
+
+/// Parameters used to open a `Connection`.
+///
+/// Unset values are passed to `mysql_real_connect` as `NULL`/`0`, which lets
+/// the client library fall back to its own defaults and option files.
+#[derive(Clone, Default)]
+pub struct ConnectOptions {
+    host: Option<String>,
+    user: Option<String>,
+    password: Option<String>,
+    database: Option<String>,
+    port: u16,
+    unix_socket: Option<String>,
+    client_flags: c_ulong,
+}
+
+impl ConnectOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn host<S: Into<String>>(mut self, host: S) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    pub fn user<S: Into<String>>(mut self, user: S) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    pub fn password<S: Into<String>>(mut self, password: S) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    pub fn database<S: Into<String>>(mut self, database: S) -> Self {
+        self.database = Some(database.into());
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    pub fn unix_socket<S: Into<String>>(mut self, path: S) -> Self {
+        self.unix_socket = Some(path.into());
+        self
+    }
+
+    /// Sets the `CLIENT_*` capability flags passed to `mysql_real_connect`.
+    pub fn client_flags(mut self, flags: c_ulong) -> Self {
+        self.client_flags = flags;
+        self
+    }
+}
+
+impl fmt::Debug for ConnectOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConnectOptions")
+            .field("host", &self.host)
+            .field("user", &self.user)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("database", &self.database)
+            .field("port", &self.port)
+            .field("unix_socket", &self.unix_socket)
+            .field("client_flags", &self.client_flags)
+            .finish()
+    }
+}
+
+/// Calls `mysql_library_init` once per process. `mysql_init` would call it
+/// on first use, but not safely when several threads do so at once. A
+/// failure shows up as `mysql_init` failing.
+pub(crate) fn library_init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| unsafe {
+        mysql_server_init(0, ptr::null_mut(), ptr::null_mut());
+    });
+}
+
+/// An owned, connected `MYSQL` handle. The handle is closed on drop.
+///
+/// The client library may reconnect automatically when it finds the
+/// connection lost, depending on `MYSQL_OPT_RECONNECT`. `cancel_handle`
+/// turns this off for good, since a reconnected connection would run under
+/// a server thread id that existing cancel handles do not know about.
+pub struct Connection {
+    raw: NonNull<MYSQL>,
+    /// The server thread id, shared with cancel handles and refreshed after
+    /// calls that may reconnect.
+    pub(crate) thread_id: Arc<AtomicU64>,
+    /// The options the connection was opened with, which cancel handles
+    /// connect with. `None` for handles adopted with `from_raw`.
+    pub(crate) opts: Option<ConnectOptions>,
+}
+
+// A `MYSQL` handle may be moved between threads as long as it is only used
+// by one of them at a time, which `&mut self` on every call guarantees.
+unsafe impl Send for Connection {}
+
+impl Connection {
+    pub fn connect(opts: &ConnectOptions) -> Result<Self> {
+        let mut conn = Connection::init()?;
+        let args = ConnectArgs::new(opts)?;
+        let connected = unsafe {
+            mysql_real_connect(conn.as_ptr(),
+                               as_ptr(&args.host),
+                               as_ptr(&args.user),
+                               as_ptr(&args.password),
+                               as_ptr(&args.database),
+                               args.port,
+                               as_ptr(&args.unix_socket),
+                               args.client_flags)
+        };
+        if connected.is_null() {
+            return Err(conn.last_error());
+        }
+        unsafe { handle_info::check_layout(conn.as_ptr())? };
+        conn.refresh_thread_id();
+        conn.opts = Some(opts.clone());
+        Ok(conn)
+    }
+
+    pub(crate) fn init() -> Result<Self> {
+        library_init();
+        let raw = unsafe { mysql_init(ptr::null_mut()) };
+        let raw = NonNull::new(raw).ok_or(Error::OutOfMemory)?;
+        Ok(Connection {
+            raw,
+            thread_id: Arc::new(AtomicU64::new(0)),
+            opts: None,
+        })
+    }
+
+    /// Takes ownership of a handle returned by `mysql_init`.
+    ///
+    /// The handle will be closed with `mysql_close` when the `Connection` is
+    /// dropped.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be a valid, connected handle that is not owned or used by
+    /// anything else. With `mysql_nonblocking`, the linked library must lay
+    /// out `MYSQL` as libmysqlclient 8.0 does, which `connect` checks.
+    pub unsafe fn from_raw(raw: *mut MYSQL) -> Self {
+        let conn = Connection {
+            raw: NonNull::new(raw).expect("MYSQL handle must not be null"),
+            thread_id: Arc::new(AtomicU64::new(0)),
+            opts: None,
+        };
+        conn.refresh_thread_id();
+        conn
+    }
+
+    pub fn as_ptr(&self) -> *mut MYSQL {
+        self.raw.as_ptr()
+    }
+
+    /// Sends a statement using `mysql_real_query`.
+    ///
+    /// If the statement produces a result set it must be retrieved with
+    /// either `store_result` or `use_result` before the next query is sent.
+    pub fn query(&mut self, sql: &str) -> Result<()> {
+        let rc = unsafe {
+            mysql_real_query(self.as_ptr(), sql.as_ptr() as *const c_char, sql.len() as c_ulong)
+        };
+        self.refresh_thread_id();
+        if rc == 0 {
+            Ok(())
+        } else {
+            Err(self.last_error())
+        }
+    }
+
+    /// Reads the entire result set of the last query into client memory.
+    ///
+    /// Returns `Ok(None)` if the last statement did not produce a result set.
+    /// The returned result does not borrow the connection, which may be used
+    /// again immediately.
+    pub fn store_result(&mut self) -> Result<Option<BufferedResult>> {
+        let res = unsafe { mysql_store_result(self.as_ptr()) };
+        if res.is_null() {
+            self.check_no_result().map(|_| None)
+        } else {
+            Ok(Some(unsafe { BufferedResult::from_raw(res) }))
+        }
+    }
+
+    /// Starts reading the result set of the last query row by row.
+    ///
+    /// Rows are read from the network as they are fetched, so the connection
+    /// cannot be used for anything else until the result is dropped. Dropping
+    /// the result reads and discards any remaining rows.
+    pub fn use_result(&mut self) -> Result<Option<StreamingResult<'_>>> {
+        let res = unsafe { mysql_use_result(self.as_ptr()) };
+        if res.is_null() {
+            self.check_no_result().map(|_| None)
+        } else {
+            Ok(Some(unsafe { StreamingResult::from_raw(res, self) }))
+        }
+    }
+
+    /// Advances to the next result of a multi-statement query, returning
+    /// `false` if there are no more results.
+    pub fn next_result(&mut self) -> Result<bool> {
+        match unsafe { mysql_next_result(self.as_ptr()) } {
+            0 => Ok(true),
+            -1 => Ok(false),
+            _ => Err(self.last_error()),
+        }
+    }
+
+    pub(crate) fn check_no_result(&self) -> Result<()> {
+        if unsafe { mysql_field_count(self.as_ptr()) } == 0 {
+            Ok(())
+        } else {
+            Err(self.last_error())
+        }
+    }
+
+    pub fn affected_rows(&self) -> u64 {
+        unsafe { mysql_affected_rows(self.as_ptr()) as u64 }
+    }
+
+    pub fn insert_id(&self) -> u64 {
+        unsafe { mysql_insert_id(self.as_ptr()) as u64 }
+    }
+
+    /// The number of warnings generated by the last statement.
+    pub fn warning_count(&self) -> u32 {
+        unsafe { mysql_warning_count(self.as_ptr()) as u32 }
+    }
+
+    /// The `SERVER_STATUS_*` flags the server sent with its last reply.
+    pub fn server_status(&self) -> c_uint {
+        unsafe { handle_info::server_status(self.as_ptr()) }
+    }
+
+    /// Whether the last query has further results to be read with
+    /// `mysql_next_result`.
+    pub fn more_results(&self) -> bool {
+        unsafe { mysql_more_results(self.as_ptr()) != 0 }
+    }
+
+    /// Whether the server reports an open transaction.
+    pub fn in_transaction(&self) -> bool {
+        self.server_status() & SERVER_STATUS_IN_TRANS != 0
+    }
+
+    /// Whether a result set or further results of the last query have not
+    /// been read yet, which leaves the connection unusable for new queries.
+    ///
+    /// With `mysql_nonblocking` or `mariadb_nonblocking`, an unread result
+    /// set of the last statement is not detected, only further results.
+    pub fn has_pending_results(&self) -> bool {
+        let ready = unsafe { handle_info::is_ready(self.as_ptr()) };
+        ready == Some(false) || self.more_results()
+    }
+
+    /// Checks that the connection is alive with `mysql_ping`.
+    pub fn ping(&mut self) -> Result<()> {
+        let rc = unsafe { mysql_ping(self.as_ptr()) };
+        self.refresh_thread_id();
+        if rc == 0 {
+            Ok(())
+        } else {
+            Err(self.last_error())
+        }
+    }
+
+    /// Resets the session state with `mysql_reset_connection`, rolling back
+    /// any transaction and dropping temporary tables, user variables and
+    /// prepared statements without re-authenticating.
+    pub fn reset(&mut self) -> Result<()> {
+        if unsafe { mysql_reset_connection(self.as_ptr()) } == 0 {
+            Ok(())
+        } else {
+            Err(self.last_error())
+        }
+    }
+
+    /// Re-authenticates with `mysql_change_user`, which also resets the
+    /// session state. Only the user, password and database of `opts` are
+    /// used.
+    pub fn change_user(&mut self, opts: &ConnectOptions) -> Result<()> {
+        let user = to_cstring(&opts.user)?;
+        let password = to_cstring(&opts.password)?;
+        let database = to_cstring(&opts.database)?;
+        let rc = unsafe {
+            mysql_change_user(self.as_ptr(), as_ptr(&user), as_ptr(&password), as_ptr(&database))
+        };
+        if rc == 0 {
+            if let Some(ref mut current) = self.opts {
+                current.user = opts.user.clone();
+                current.password = opts.password.clone();
+                current.database = opts.database.clone();
+            }
+            Ok(())
+        } else {
+            Err(self.last_error())
+        }
+    }
+
+    /// The client's `max_allowed_packet` option, which bounds the size of a
+    /// single packet sent to or received from the server.
+    pub fn max_allowed_packet(&self) -> Result<u64> {
+        let mut value: c_ulong = 0;
+        let rc = unsafe {
+            mysql_get_option(self.as_ptr(),
+                             mysql_option::MYSQL_OPT_MAX_ALLOWED_PACKET,
+                             &mut value as *mut c_ulong as *const c_void)
+        };
+        if rc == 0 {
+            Ok(value as u64)
+        } else {
+            Err(self.last_error())
+        }
+    }
+
+    /// The id of the server thread serving this connection, which changes
+    /// when the client library reconnects automatically.
+    pub fn thread_id(&self) -> u64 {
+        unsafe { mysql_thread_id(self.as_ptr()) as u64 }
+    }
+
+    pub(crate) fn refresh_thread_id(&self) {
+        self.thread_id.store(self.thread_id(), Ordering::Relaxed);
+    }
+
+    pub fn errno(&self) -> u32 {
+        unsafe { mysql_errno(self.as_ptr()) as u32 }
+    }
+
+    pub(crate) fn last_error(&self) -> Error {
+        unsafe { MysqlError::from_connection(self.as_ptr()).into() }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        unsafe { mysql_close(self.as_ptr()) }
+    }
+}
+
+/// The arguments of `mysql_real_connect`, converted to C strings that stay
+/// alive across the repeated calls of a nonblocking connect.
+pub(crate) struct ConnectArgs {
+    pub(crate) host: Option<CString>,
+    pub(crate) user: Option<CString>,
+    pub(crate) password: Option<CString>,
+    pub(crate) database: Option<CString>,
+    pub(crate) port: c_uint,
+    pub(crate) unix_socket: Option<CString>,
+    pub(crate) client_flags: c_ulong,
+}
+
+impl ConnectArgs {
+    pub(crate) fn new(opts: &ConnectOptions) -> Result<Self> {
+        Ok(ConnectArgs {
+            host: to_cstring(&opts.host)?,
+            user: to_cstring(&opts.user)?,
+            password: to_cstring(&opts.password)?,
+            database: to_cstring(&opts.database)?,
+            port: opts.port as c_uint,
+            unix_socket: to_cstring(&opts.unix_socket)?,
+            client_flags: opts.client_flags,
+        })
+    }
+}
+
+fn to_cstring(value: &Option<String>) -> Result<Option<CString>> {
+    match *value {
+        Some(ref s) => Ok(Some(CString::new(s.as_bytes())?)),
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn as_ptr(value: &Option<CString>) -> *const c_char {
+    value.as_ref().map_or(ptr::null(), |s| s.as_ptr())
+}
@@ -0,0 +1,33 @@
+//! Constants from `mysql_com.h` that are defined as preprocessor macros and
+//! therefore missing from the generated bindings.
+
+use std::os::raw::{c_uint, c_ulong};
+
+pub const NOT_NULL_FLAG: c_uint = 1;
+pub const PRI_KEY_FLAG: c_uint = 2;
+pub const UNIQUE_KEY_FLAG: c_uint = 4;
+pub const MULTIPLE_KEY_FLAG: c_uint = 8;
+pub const BLOB_FLAG: c_uint = 16;
+pub const UNSIGNED_FLAG: c_uint = 32;
+pub const ZEROFILL_FLAG: c_uint = 64;
+pub const BINARY_FLAG: c_uint = 128;
+pub const ENUM_FLAG: c_uint = 256;
+pub const AUTO_INCREMENT_FLAG: c_uint = 512;
+pub const TIMESTAMP_FLAG: c_uint = 1024;
+pub const SET_FLAG: c_uint = 2048;
+pub const NO_DEFAULT_VALUE_FLAG: c_uint = 4096;
+pub const ON_UPDATE_NOW_FLAG: c_uint = 8192;
+pub const NUM_FLAG: c_uint = 32768;
+
+/// The `charsetnr` of columns holding binary strings.
+pub const BINARY_CHARSET_NR: c_uint = 63;
+
+pub const SERVER_STATUS_IN_TRANS: c_uint = 1;
+pub const SERVER_STATUS_AUTOCOMMIT: c_uint = 2;
+pub const SERVER_MORE_RESULTS_EXISTS: c_uint = 8;
+pub const SERVER_STATUS_NO_BACKSLASH_ESCAPES: c_uint = 512;
+pub const SERVER_PS_OUT_PARAMS: c_uint = 4096;
+
+pub const CLIENT_MULTI_STATEMENTS: c_ulong = 1 << 16;
+pub const CLIENT_MULTI_RESULTS: c_ulong = 1 << 17;
+pub const CLIENT_PS_MULTI_RESULTS: c_ulong = 1 << 18;
@@ -0,0 +1,380 @@
+//! Construction and validation of `MYSQL_TIME` values.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::str;
+
+#[cfg(any(feature = "chrono", feature = "time"))]
+use params::Param;
+#[cfg(any(feature = "chrono", feature = "time"))]
+use enum_field_types;
+use {MYSQL_TIME, enum_mysql_timestamp_type};
+use enum_mysql_timestamp_type::*;
+
+/// The largest magnitude of a `TIME` value, `838:59:59`.
+pub const MAX_TIME_HOURS: u32 = 838;
+
+/// A `MYSQL_TIME` as handed to the client library.
+///
+/// libmysqlclient 8.0.19 appended `time_zone_displacement` to `MYSQL_TIME`,
+/// so binary temporal values are written past the end of the generated
+/// struct. The padding leaves room for it; the fields before it are
+/// unchanged.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TimeBuffer {
+    pub(crate) time: MYSQL_TIME,
+    _time_zone_displacement: u64,
+}
+
+impl TimeBuffer {
+    pub(crate) fn new(time: MYSQL_TIME) -> Self {
+        TimeBuffer {
+            time,
+            _time_zone_displacement: 0,
+        }
+    }
+}
+
+/// Why a `MYSQL_TIME` could not be converted to or from another type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeConversionError {
+    /// The value is a zero date such as `0000-00-00`, or has a zero month or
+    /// day, which MySQL allows but calendar types cannot represent.
+    ZeroDate,
+    /// The date or time fields do not form a valid value.
+    Invalid,
+    /// The value is outside of the range of the target type, or of a MySQL
+    /// `TIME` (`-838:59:59` to `838:59:59`).
+    OutOfRange,
+    /// The `time_type` of the value cannot be converted to the target type.
+    WrongType(enum_mysql_timestamp_type),
+}
+
+impl fmt::Display for TimeConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TimeConversionError::ZeroDate => f.write_str("zero dates cannot be converted"),
+            TimeConversionError::Invalid => f.write_str("invalid date or time"),
+            TimeConversionError::OutOfRange => f.write_str("date or time out of range"),
+            TimeConversionError::WrongType(ty) => write!(f, "unexpected time type {:?}", ty),
+        }
+    }
+}
+
+impl StdError for TimeConversionError {}
+
+impl MYSQL_TIME {
+    /// A `DATE` value.
+    pub fn date(year: u32, month: u32, day: u32) -> Self {
+        let mut t = empty_time(MYSQL_TIMESTAMP_DATE);
+        t.year = year;
+        t.month = month;
+        t.day = day;
+        t
+    }
+
+    /// A `DATETIME` or `TIMESTAMP` value.
+    pub fn datetime(year: u32,
+                    month: u32,
+                    day: u32,
+                    hour: u32,
+                    minute: u32,
+                    second: u32,
+                    microsecond: u32)
+                    -> Self {
+        let mut t = MYSQL_TIME::date(year, month, day);
+        t.time_type = MYSQL_TIMESTAMP_DATETIME;
+        t.hour = hour;
+        t.minute = minute;
+        t.second = second;
+        t.second_part = microsecond as _;
+        t
+    }
+
+    /// A `TIME` value, which is a duration of up to 838 hours in either
+    /// direction rather than a time of day.
+    pub fn time(negative: bool, hours: u32, minutes: u32, seconds: u32, microseconds: u32) -> Self {
+        let mut t = empty_time(MYSQL_TIMESTAMP_TIME);
+        t.neg = negative as _;
+        t.hour = hours;
+        t.minute = minutes;
+        t.second = seconds;
+        t.second_part = microseconds as _;
+        t
+    }
+
+    /// A value with `MYSQL_TIMESTAMP_NONE`, as used for unset values.
+    pub fn none() -> Self {
+        empty_time(MYSQL_TIMESTAMP_NONE)
+    }
+
+    /// A value with `MYSQL_TIMESTAMP_ERROR`, as returned by the client
+    /// library for values it could not parse.
+    pub fn error() -> Self {
+        empty_time(MYSQL_TIMESTAMP_ERROR)
+    }
+
+    /// Whether the date part has a zero year, month or day, as in
+    /// `0000-00-00` or `2024-00-00`.
+    pub fn is_zero_date(&self) -> bool {
+        self.year == 0 || self.month == 0 || self.day == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.neg != 0
+    }
+
+    pub fn microseconds(&self) -> u32 {
+        self.second_part as u32
+    }
+
+    /// Checks that the value is a `DATE` or `DATETIME` with a non-zero date.
+    pub fn check_date(&self) -> Result<(), TimeConversionError> {
+        match self.time_type {
+            MYSQL_TIMESTAMP_DATE | MYSQL_TIMESTAMP_DATETIME => {}
+            ty => return Err(TimeConversionError::WrongType(ty)),
+        }
+        if self.is_zero_date() {
+            Err(TimeConversionError::ZeroDate)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The magnitude of a `TIME` value in microseconds, checked against the
+    /// range of the `TIME` type.
+    pub fn time_micros(&self) -> Result<i64, TimeConversionError> {
+        if self.time_type != MYSQL_TIMESTAMP_TIME {
+            return Err(TimeConversionError::WrongType(self.time_type));
+        }
+        if self.minute > 59 || self.second > 59 || self.second_part >= 1_000_000 {
+            return Err(TimeConversionError::Invalid);
+        }
+        if self.hour > MAX_TIME_HOURS {
+            return Err(TimeConversionError::OutOfRange);
+        }
+        let seconds = self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64;
+        let micros = seconds * 1_000_000 + self.second_part as i64;
+        Ok(if self.is_negative() { -micros } else { micros })
+    }
+
+    /// Builds a `TIME` value from a signed number of microseconds.
+    pub fn from_time_micros(micros: i64) -> Result<Self, TimeConversionError> {
+        let magnitude = micros.unsigned_abs();
+        let seconds = magnitude / 1_000_000;
+        let hours = seconds / 3600;
+        if hours > MAX_TIME_HOURS as u64 {
+            return Err(TimeConversionError::OutOfRange);
+        }
+        Ok(MYSQL_TIME::time(micros < 0,
+                            hours as u32,
+                            (seconds / 60 % 60) as u32,
+                            (seconds % 60) as u32,
+                            (magnitude % 1_000_000) as u32))
+    }
+}
+
+impl PartialEq for MYSQL_TIME {
+    fn eq(&self, other: &Self) -> bool {
+        self.year == other.year && self.month == other.month && self.day == other.day &&
+        self.hour == other.hour && self.minute == other.minute &&
+        self.second == other.second && self.second_part == other.second_part &&
+        self.neg == other.neg && self.time_type == other.time_type
+    }
+}
+
+/// Formats the value as MySQL does in the text protocol, such as
+/// `2024-01-31`, `-12:30:00` or `2024-01-31 12:30:00.250000`. Fractional
+/// seconds are only written when non-zero.
+impl fmt::Display for MYSQL_TIME {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.time_type {
+            MYSQL_TIMESTAMP_DATE => {
+                return write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day);
+            }
+            MYSQL_TIMESTAMP_TIME => {
+                let sign = if self.is_negative() { "-" } else { "" };
+                let hours = self.day * 24 + self.hour;
+                write!(f, "{}{:02}:{:02}:{:02}", sign, hours, self.minute, self.second)?;
+            }
+            _ => {
+                write!(f,
+                       "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                       self.year,
+                       self.month,
+                       self.day,
+                       self.hour,
+                       self.minute,
+                       self.second)?;
+            }
+        }
+        if self.second_part != 0 {
+            write!(f, ".{:06}", self.second_part)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn empty_time(time_type: enum_mysql_timestamp_type) -> MYSQL_TIME {
+    MYSQL_TIME {
+        year: 0,
+        month: 0,
+        day: 0,
+        hour: 0,
+        minute: 0,
+        second: 0,
+        second_part: 0,
+        neg: 0,
+        time_type,
+    }
+}
+
+/// A parameter for a value that `MYSQL_TIME` cannot hold, such as a year
+/// before 0. It is sent as text, so the server rejects it, or clips it with
+/// a warning outside strict mode, as it would the same literal.
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub(crate) fn text_param(text: String) -> Param {
+    Param::bytes(enum_field_types::MYSQL_TYPE_STRING, text.into_bytes())
+}
+
+/// Formats a duration as `TIME` text, such as `-900:00:00.000001`, for
+/// durations beyond `838:59:59.999999`.
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub(crate) fn duration_text(negative: bool, seconds: u64, microseconds: u32) -> String {
+    format!("{}{}:{:02}:{:02}.{:06}",
+            if negative { "-" } else { "" },
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60,
+            microseconds)
+}
+
+/// Parses `YYYY-MM-DD` as sent for `DATE` columns.
+pub(crate) fn parse_date(s: &str) -> Option<MYSQL_TIME> {
+    let mut t = empty_time(enum_mysql_timestamp_type::MYSQL_TIMESTAMP_DATE);
+    parse_date_into(s, &mut t)?;
+    Some(t)
+}
+
+/// Parses `YYYY-MM-DD hh:mm:ss[.ffffff]` as sent for `DATETIME` and
+/// `TIMESTAMP` columns.
+pub(crate) fn parse_datetime(s: &str) -> Option<MYSQL_TIME> {
+    let mut t = empty_time(enum_mysql_timestamp_type::MYSQL_TIMESTAMP_DATETIME);
+    let mut parts = s.splitn(2, &[' ', 'T'][..]);
+    parse_date_into(parts.next()?, &mut t)?;
+    match parts.next() {
+        Some(time) => parse_time_of_day_into(time, &mut t)?,
+        None => return None,
+    }
+    if t.hour > 23 {
+        return None;
+    }
+    Some(t)
+}
+
+/// Parses `[-]h..h:mm:ss[.ffffff]` as sent for `TIME` columns, whose hours
+/// may exceed 24 and which may be negative.
+pub(crate) fn parse_time(s: &str) -> Option<MYSQL_TIME> {
+    let mut t = empty_time(enum_mysql_timestamp_type::MYSQL_TIMESTAMP_TIME);
+    let s = match s.strip_prefix('-') {
+        Some(rest) => {
+            t.neg = 1;
+            rest
+        }
+        None => s,
+    };
+    parse_time_of_day_into(s, &mut t)?;
+    Some(t)
+}
+
+/// Parses `YYYY` as sent for `YEAR` columns into a date with a zero month
+/// and day.
+pub(crate) fn parse_year(s: &str) -> Option<MYSQL_TIME> {
+    let mut t = empty_time(enum_mysql_timestamp_type::MYSQL_TIMESTAMP_DATE);
+    t.year = parse_digits(s)?;
+    Some(t)
+}
+
+fn parse_date_into(s: &str, t: &mut MYSQL_TIME) -> Option<()> {
+    let mut parts = s.split('-');
+    t.year = parse_digits(parts.next()?)?;
+    t.month = parse_digits(parts.next()?)?;
+    t.day = parse_digits(parts.next()?)?;
+    if parts.next().is_some() || t.month > 12 || t.day > 31 {
+        return None;
+    }
+    Some(())
+}
+
+fn parse_time_of_day_into(s: &str, t: &mut MYSQL_TIME) -> Option<()> {
+    let mut frac = s.splitn(2, '.');
+    let mut parts = frac.next()?.split(':');
+    t.hour = parse_digits(parts.next()?)?;
+    t.minute = parse_digits(parts.next()?)?;
+    t.second = parse_digits(parts.next()?)?;
+    if parts.next().is_some() || t.minute > 59 || t.second > 59 {
+        return None;
+    }
+    if let Some(digits) = frac.next() {
+        if digits.is_empty() || digits.len() > 6 {
+            return None;
+        }
+        let value: u32 = parse_digits(digits)?;
+        t.second_part = (value * 10u32.pow(6 - digits.len() as u32)) as _;
+    }
+    Some(())
+}
+
+fn parse_digits(s: &str) -> Option<u32> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_date("2024-01-31"), Some(MYSQL_TIME::date(2024, 1, 31)));
+        assert_eq!(parse_date("0000-00-00"), Some(MYSQL_TIME::date(0, 0, 0)));
+        assert_eq!(parse_date("9999-12-31"), Some(MYSQL_TIME::date(9999, 12, 31)));
+        for s in &["", "2024-01", "2024-01-31-1", "2024-13-01", "2024-01-32", "2024-1a-01",
+                   "2024--01", "+2024-01-01", "2024-01-31 00:00:00"] {
+            assert_eq!(parse_date(s), None, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn datetimes() {
+        assert_eq!(parse_datetime("2024-01-31 12:30:05"),
+                   Some(MYSQL_TIME::datetime(2024, 1, 31, 12, 30, 5, 0)));
+        assert_eq!(parse_datetime("2024-01-31T23:59:59.5"),
+                   Some(MYSQL_TIME::datetime(2024, 1, 31, 23, 59, 59, 500_000)));
+        assert_eq!(parse_datetime("2024-01-31 00:00:00.000001"),
+                   Some(MYSQL_TIME::datetime(2024, 1, 31, 0, 0, 0, 1)));
+        assert_eq!(parse_datetime("0000-00-00 00:00:00"),
+                   Some(MYSQL_TIME::datetime(0, 0, 0, 0, 0, 0, 0)));
+        for s in &["2024-01-31", "2024-01-31 ", "2024-01-31 24:00:00", "2024-01-31 99:00:00",
+                   "2024-01-31 12:60:00", "2024-01-31 12:00:60", "2024-01-31 12:00",
+                   "2024-01-31 12:00:00.", "2024-01-31 12:00:00.1234567",
+                   "2024-01-31 12:00:00:00", "2024-01-32 12:00:00"] {
+            assert_eq!(parse_datetime(s), None, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(parse_time("12:30:05"), Some(MYSQL_TIME::time(false, 12, 30, 5, 0)));
+        assert_eq!(parse_time("-838:59:59"), Some(MYSQL_TIME::time(true, 838, 59, 59, 0)));
+        assert_eq!(parse_time("100:00:00.25"), Some(MYSQL_TIME::time(false, 100, 0, 0, 250_000)));
+        assert_eq!(parse_time("-00:00:01.000001"), Some(MYSQL_TIME::time(true, 0, 0, 1, 1)));
+        for s in &["", "-", "12:30", "12:60:00", "12:00:60", "--12:00:00", "+12:00:00",
+                   "12:00:00.", "12:00:00.1234567", "12:00:00:00", "1 12:00:00"] {
+            assert_eq!(parse_time(s), None, "{:?}", s);
+        }
+    }
+}
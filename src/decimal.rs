@@ -0,0 +1,213 @@
+//! Precision and scale checks for exact `DECIMAL` values.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use column::Column;
+use decode::DecimalString;
+use params::{Param, ToSqlParam};
+use enum_field_types::{self, MYSQL_TYPE_DECIMAL, MYSQL_TYPE_NEWDECIMAL};
+
+/// Why a decimal value does not fit a `DECIMAL(precision, scale)` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecimalError {
+    /// The value has more integer digits than `precision - scale`.
+    Overflow,
+    /// The value has more significant fractional digits than `scale`, and
+    /// would be rounded by the server.
+    Rounding,
+    /// The value is not a plain decimal number.
+    Invalid,
+}
+
+impl fmt::Display for DecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecimalError::Overflow => f.write_str("decimal value overflows the column precision"),
+            DecimalError::Rounding => {
+                f.write_str("decimal value would be rounded to the column scale")
+            }
+            DecimalError::Invalid => f.write_str("invalid decimal value"),
+        }
+    }
+}
+
+impl StdError for DecimalError {}
+
+/// The precision and scale of a `DECIMAL` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalSpec {
+    pub precision: u32,
+    pub scale: u32,
+}
+
+impl DecimalSpec {
+    pub fn new(precision: u32, scale: u32) -> Self {
+        DecimalSpec { precision, scale }
+    }
+
+    /// Derives the precision and scale of a `DECIMAL` column from its
+    /// metadata.
+    ///
+    /// `MYSQL_FIELD::length` is the display width of the column, which counts
+    /// the decimal point and, for signed columns, the minus sign in addition
+    /// to the digits. Returns `None` for other column types.
+    pub fn from_column(column: &Column) -> Option<Self> {
+        match column.column_type() {
+            MYSQL_TYPE_DECIMAL | MYSQL_TYPE_NEWDECIMAL => {}
+            _ => return None,
+        }
+        let scale = column.decimals();
+        let mut precision = column.display_length() as u32;
+        if scale > 0 {
+            precision = precision.saturating_sub(1);
+        }
+        if !column.is_unsigned() {
+            precision = precision.saturating_sub(1);
+        }
+        Some(DecimalSpec::new(precision, scale))
+    }
+
+    /// Checks that a plain decimal string such as `-123.4500` fits into the
+    /// column without overflowing or being rounded.
+    pub fn check(&self, plain: &str) -> Result<(), DecimalError> {
+        let (int_digits, frac_digits) = significant_digits(plain)?;
+        if frac_digits > self.scale {
+            Err(DecimalError::Rounding)
+        } else if int_digits > self.precision.saturating_sub(self.scale) {
+            Err(DecimalError::Overflow)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Splits a plain decimal string such as `-123.45` into its integer and
+/// fractional digits. Returns `None` for anything else, including a `+`
+/// sign, an exponent, or an empty integer or fractional part.
+pub(crate) fn split_plain(s: &str) -> Option<(&str, &str)> {
+    let unsigned = s.strip_prefix('-').unwrap_or(s);
+    let mut parts = unsigned.splitn(2, '.');
+    let int_part = parts.next().unwrap_or("");
+    let frac_part = parts.next();
+    let all_digits = |p: &str| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit());
+    if all_digits(int_part) && frac_part.is_none_or(all_digits) {
+        Some((int_part, frac_part.unwrap_or("")))
+    } else {
+        None
+    }
+}
+
+/// Counts the integer digits without leading zeros and the fractional digits
+/// without trailing zeros of a plain decimal string.
+fn significant_digits(plain: &str) -> Result<(u32, u32), DecimalError> {
+    let (int_part, frac_part) = split_plain(plain).ok_or(DecimalError::Invalid)?;
+    let int_digits = int_part.trim_start_matches('0').len() as u32;
+    let frac_digits = frac_part.trim_end_matches('0').len() as u32;
+    Ok((int_digits, frac_digits))
+}
+
+/// Exact decimal types that can be rendered without an exponent.
+pub trait SqlDecimal {
+    /// Renders the value as a plain decimal string, such as `-123.45`.
+    fn to_plain_string(&self) -> String;
+}
+
+impl SqlDecimal for DecimalString {
+    fn to_plain_string(&self) -> String {
+        self.0.clone()
+    }
+}
+
+/// Builds a `MYSQL_TYPE_NEWDECIMAL` parameter, failing instead of letting the
+/// server round or reject a value that does not fit `spec`.
+pub fn checked_param<D: SqlDecimal>(value: &D, spec: DecimalSpec) -> Result<Param, DecimalError> {
+    let plain = value.to_plain_string();
+    spec.check(&plain)?;
+    Ok(Param::bytes(enum_field_types::MYSQL_TYPE_NEWDECIMAL, plain.into_bytes()))
+}
+
+impl ToSqlParam for DecimalString {
+    fn to_sql_param(&self) -> Param {
+        Param::bytes(enum_field_types::MYSQL_TYPE_NEWDECIMAL, self.0.as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enum_field_types::MYSQL_TYPE_LONG;
+    use {BINARY_CHARSET_NR, UNSIGNED_FLAG};
+
+    fn decimal_column(length: u64, decimals: u32, flags: u32) -> Column {
+        Column::for_test(MYSQL_TYPE_NEWDECIMAL, flags, BINARY_CHARSET_NR)
+            .with_length(length, decimals)
+    }
+
+    #[test]
+    fn spec_from_column_metadata() {
+        // DECIMAL(5,2) is 7 wide: five digits, the point and the sign.
+        assert_eq!(DecimalSpec::from_column(&decimal_column(7, 2, 0)),
+                   Some(DecimalSpec::new(5, 2)));
+        assert_eq!(DecimalSpec::from_column(&decimal_column(6, 2, UNSIGNED_FLAG)),
+                   Some(DecimalSpec::new(5, 2)));
+        assert_eq!(DecimalSpec::from_column(&decimal_column(11, 0, 0)),
+                   Some(DecimalSpec::new(10, 0)));
+        assert_eq!(DecimalSpec::from_column(&decimal_column(65, 0, UNSIGNED_FLAG)),
+                   Some(DecimalSpec::new(65, 0)));
+        let long = Column::for_test(MYSQL_TYPE_LONG, 0, BINARY_CHARSET_NR).with_length(11, 0);
+        assert_eq!(DecimalSpec::from_column(&long), None);
+    }
+
+    #[test]
+    fn precision_overflow() {
+        let spec = DecimalSpec::new(5, 2);
+        assert_eq!(spec.check("999.99"), Ok(()));
+        assert_eq!(spec.check("-999.99"), Ok(()));
+        assert_eq!(spec.check("1000"), Err(DecimalError::Overflow));
+        assert_eq!(spec.check("-1000.0"), Err(DecimalError::Overflow));
+        assert_eq!(spec.check("000999"), Ok(()));
+        assert_eq!(DecimalSpec::new(3, 3).check("0.123"), Ok(()));
+        assert_eq!(DecimalSpec::new(3, 3).check("1.0"), Err(DecimalError::Overflow));
+    }
+
+    #[test]
+    fn scale_rounding() {
+        let spec = DecimalSpec::new(5, 2);
+        assert_eq!(spec.check("1.234"), Err(DecimalError::Rounding));
+        assert_eq!(spec.check("1.001"), Err(DecimalError::Rounding));
+        assert_eq!(DecimalSpec::new(4, 0).check("1.5"), Err(DecimalError::Rounding));
+    }
+
+    #[test]
+    fn trailing_zeros_are_not_significant() {
+        let spec = DecimalSpec::new(5, 2);
+        assert_eq!(spec.check("1.2000000"), Ok(()));
+        assert_eq!(DecimalSpec::new(4, 0).check("1234.000"), Ok(()));
+    }
+
+    #[test]
+    fn negative_zero() {
+        let spec = DecimalSpec::new(1, 0);
+        assert_eq!(spec.check("-0"), Ok(()));
+        assert_eq!(spec.check("-0.000"), Ok(()));
+        assert_eq!(DecimalSpec::new(0, 0).check("-0"), Ok(()));
+    }
+
+    #[test]
+    fn invalid_text() {
+        let spec = DecimalSpec::new(10, 2);
+        for value in &["", "-", "+1", "--1", "1e2", ".5", "5.", "1.2.3", " 1"] {
+            assert_eq!(spec.check(value), Err(DecimalError::Invalid), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn checked_params() {
+        let spec = DecimalSpec::new(5, 2);
+        let param = checked_param(&DecimalString("-1.50".to_owned()), spec).unwrap();
+        assert_eq!(param.buffer_type(), MYSQL_TYPE_NEWDECIMAL);
+        assert_eq!(checked_param(&DecimalString("1.005".to_owned()), spec).unwrap_err(),
+                   DecimalError::Rounding);
+    }
+}
@@ -0,0 +1,423 @@
+//! Decoding of text protocol cells into Rust values.
+
+use std::any::type_name;
+use std::error::Error as StdError;
+use std::fmt;
+use std::str;
+
+use column::Column;
+use datetime;
+use decimal;
+#[cfg(feature = "encoding_rs")]
+use encoding_support;
+#[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+use decimal::DecimalSpec;
+#[cfg(feature = "encoding_rs")]
+use BINARY_CHARSET_NR;
+use {MYSQL_TIME, enum_field_types};
+use enum_field_types::*;
+
+/// Why a cell could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// The cell was `NULL` but the target type cannot represent it.
+    UnexpectedNull,
+    /// The column type cannot be decoded into the target type.
+    TypeMismatch { target: &'static str },
+    /// The value does not fit into the target type.
+    OutOfRange { target: &'static str },
+    /// The cell is not a valid textual representation of its column type.
+    Invalid(String),
+    /// The cell is not valid text in its column's character set. Bytes up
+    /// to `valid_up_to` decoded correctly.
+    InvalidText {
+        charset: &'static str,
+        valid_up_to: usize,
+    },
+    /// The column's collation id is unknown or its character set cannot be
+    /// decoded.
+    UnsupportedCharset(u32),
+}
+
+/// An error decoding a cell, naming the column and its `enum_field_types`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub column: String,
+    pub column_type: enum_field_types,
+    pub kind: DecodeErrorKind,
+}
+
+impl DecodeError {
+    pub fn new(column: &Column, kind: DecodeErrorKind) -> Self {
+        DecodeError {
+            column: column.name().into_owned(),
+            column_type: column.column_type(),
+            kind,
+        }
+    }
+
+    fn mismatch<T>(column: &Column) -> Self {
+        DecodeError::new(column, DecodeErrorKind::TypeMismatch { target: type_name::<T>() })
+    }
+
+    pub(crate) fn out_of_range<T>(column: &Column) -> Self {
+        DecodeError::new(column, DecodeErrorKind::OutOfRange { target: type_name::<T>() })
+    }
+
+    fn invalid(column: &Column, value: &[u8]) -> Self {
+        let value = String::from_utf8_lossy(value).into_owned();
+        DecodeError::new(column, DecodeErrorKind::Invalid(value))
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot decode column `{}` of type {:?}: ", self.column, self.column_type)?;
+        match self.kind {
+            DecodeErrorKind::UnexpectedNull => f.write_str("unexpected NULL"),
+            DecodeErrorKind::TypeMismatch { target } => write!(f, "cannot be read as {}", target),
+            DecodeErrorKind::OutOfRange { target } => {
+                write!(f, "value out of range for {}", target)
+            }
+            DecodeErrorKind::Invalid(ref value) => write!(f, "invalid value {:?}", value),
+            DecodeErrorKind::InvalidText { charset, valid_up_to } => {
+                write!(f, "invalid {} text after byte {}", charset, valid_up_to)
+            }
+            DecodeErrorKind::UnsupportedCharset(id) => {
+                write!(f, "unsupported character set (collation id {})", id)
+            }
+        }
+    }
+}
+
+impl StdError for DecodeError {}
+
+/// Types that can be decoded from a text protocol cell, as returned by
+/// `mysql_fetch_row`.
+///
+/// `value` is `None` for SQL `NULL`. Implementations check the column's
+/// `enum_field_types` and flags before interpreting the bytes.
+pub trait FromSqlText: Sized {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError>;
+}
+
+fn non_null<'a>(column: &Column, value: Option<&'a [u8]>) -> Result<&'a [u8], DecodeError> {
+    value.ok_or_else(|| DecodeError::new(column, DecodeErrorKind::UnexpectedNull))
+}
+
+fn ascii<'a>(column: &Column, value: &'a [u8]) -> Result<&'a str, DecodeError> {
+    match str::from_utf8(value) {
+        Ok(s) if value.is_ascii() => Ok(s),
+        _ => Err(DecodeError::invalid(column, value)),
+    }
+}
+
+impl<T: FromSqlText> FromSqlText for Option<T> {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        match value {
+            Some(_) => T::from_sql_text(column, value).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+fn is_integer(column_type: enum_field_types) -> bool {
+    matches!(column_type,
+             MYSQL_TYPE_TINY | MYSQL_TYPE_SHORT | MYSQL_TYPE_INT24 | MYSQL_TYPE_LONG |
+             MYSQL_TYPE_LONGLONG | MYSQL_TYPE_YEAR)
+}
+
+/// Decodes an integer column as `i64` or `u64` depending on `UNSIGNED_FLAG`,
+/// then narrows it to `T`. `BIT` columns arrive as big-endian bytes.
+fn decode_integer<T>(column: &Column, value: Option<&[u8]>) -> Result<T, DecodeError>
+    where T: ::std::convert::TryFrom<i64> + ::std::convert::TryFrom<u64>
+{
+    let value = non_null(column, value)?;
+    if column.column_type() == MYSQL_TYPE_BIT {
+        if value.len() > 8 {
+            return Err(DecodeError::out_of_range::<T>(column));
+        }
+        let bits = value.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+        return T::try_from(bits).map_err(|_| DecodeError::out_of_range::<T>(column));
+    }
+    if !is_integer(column.column_type()) {
+        return Err(DecodeError::mismatch::<T>(column));
+    }
+    let s = ascii(column, value)?;
+    let narrowed = if column.is_unsigned() {
+        let n: u64 = s.parse().map_err(|_| DecodeError::invalid(column, value))?;
+        T::try_from(n).ok()
+    } else {
+        let n: i64 = s.parse().map_err(|_| DecodeError::invalid(column, value))?;
+        T::try_from(n).ok()
+    };
+    narrowed.ok_or_else(|| DecodeError::out_of_range::<T>(column))
+}
+
+macro_rules! integer_from_sql_text {
+    ($($ty:ty)*) => {$(
+        impl FromSqlText for $ty {
+            fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+                decode_integer(column, value)
+            }
+        }
+    )*}
+}
+
+integer_from_sql_text!(i8 i16 i32 i64 u8 u16 u32 u64);
+
+fn decode_float<T: str::FromStr>(column: &Column, value: Option<&[u8]>) -> Result<T, DecodeError> {
+    let value = non_null(column, value)?;
+    match column.column_type() {
+        MYSQL_TYPE_FLOAT | MYSQL_TYPE_DOUBLE | MYSQL_TYPE_DECIMAL | MYSQL_TYPE_NEWDECIMAL => {}
+        ty if is_integer(ty) => {}
+        _ => return Err(DecodeError::mismatch::<T>(column)),
+    }
+    ascii(column, value)?.parse().map_err(|_| DecodeError::invalid(column, value))
+}
+
+impl FromSqlText for f32 {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        decode_float(column, value)
+    }
+}
+
+impl FromSqlText for f64 {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        decode_float(column, value)
+    }
+}
+
+/// `BOOL` is an alias for `TINYINT(1)`; any non-zero value is `true`.
+impl FromSqlText for bool {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        match column.column_type() {
+            MYSQL_TYPE_TINY | MYSQL_TYPE_BIT => i64::from_sql_text(column, value).map(|n| n != 0),
+            _ => Err(DecodeError::mismatch::<bool>(column)),
+        }
+    }
+}
+
+fn is_binary_string(column: &Column) -> bool {
+    match column.column_type() {
+        MYSQL_TYPE_VARCHAR | MYSQL_TYPE_VAR_STRING | MYSQL_TYPE_STRING | MYSQL_TYPE_TINY_BLOB |
+        MYSQL_TYPE_MEDIUM_BLOB | MYSQL_TYPE_LONG_BLOB | MYSQL_TYPE_BLOB => column.is_binary(),
+        MYSQL_TYPE_BIT | MYSQL_TYPE_GEOMETRY => true,
+        _ => false,
+    }
+}
+
+/// Any column except binary strings, `BIT` and `GEOMETRY` can be read as
+/// text. This includes `JSON`, which reports the binary character set but is
+/// always sent as UTF-8.
+///
+/// With the `encoding_rs` feature, text columns are decoded from their
+/// column's character set. Otherwise all text is expected to be UTF-8.
+impl FromSqlText for String {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        let value = non_null(column, value)?;
+        if is_binary_string(column) {
+            return Err(DecodeError::mismatch::<String>(column));
+        }
+        #[cfg(feature = "encoding_rs")]
+        {
+            if column.charset_id() != BINARY_CHARSET_NR {
+                return encoding_support::decode_text(column, value);
+            }
+        }
+        String::from_utf8(value.to_vec()).map_err(|_| DecodeError::invalid(column, value))
+    }
+}
+
+/// Any column can be read as its raw bytes.
+impl FromSqlText for Vec<u8> {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        non_null(column, value).map(|v| v.to_vec())
+    }
+}
+
+/// The exact textual representation of a `DECIMAL` value, such as
+/// `-1234.5600`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DecimalString(pub String);
+
+impl FromSqlText for DecimalString {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        let value = non_null(column, value)?;
+        match column.column_type() {
+            MYSQL_TYPE_DECIMAL | MYSQL_TYPE_NEWDECIMAL => {}
+            ty if is_integer(ty) => {}
+            _ => return Err(DecodeError::mismatch::<DecimalString>(column)),
+        }
+        let s = ascii(column, value)?;
+        if decimal::split_plain(s).is_some() {
+            Ok(DecimalString(s.to_owned()))
+        } else {
+            Err(DecodeError::invalid(column, value))
+        }
+    }
+}
+
+/// Decodes a `DECIMAL` cell into its text, checking it against the
+/// precision and scale of the column.
+#[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+pub(crate) fn checked_decimal<T>(column: &Column,
+                                 value: Option<&[u8]>)
+                                 -> Result<String, DecodeError> {
+    let DecimalString(plain) = DecimalString::from_sql_text(column, value)?;
+    if let Some(spec) = DecimalSpec::from_column(column) {
+        spec.check(&plain).map_err(|_| DecodeError::out_of_range::<T>(column))?;
+    }
+    Ok(plain)
+}
+
+/// Decodes `DATE`, `TIME`, `DATETIME`, `TIMESTAMP` and `YEAR` columns,
+/// setting `time_type` accordingly. Zero dates such as `0000-00-00` are
+/// returned as-is.
+impl FromSqlText for MYSQL_TIME {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        let value = non_null(column, value)?;
+        let s = ascii(column, value)?;
+        let parsed = match column.column_type() {
+            MYSQL_TYPE_DATE | MYSQL_TYPE_NEWDATE => datetime::parse_date(s),
+            MYSQL_TYPE_TIME | MYSQL_TYPE_TIME2 => datetime::parse_time(s),
+            MYSQL_TYPE_DATETIME | MYSQL_TYPE_DATETIME2 | MYSQL_TYPE_TIMESTAMP |
+            MYSQL_TYPE_TIMESTAMP2 => datetime::parse_datetime(s),
+            MYSQL_TYPE_YEAR => datetime::parse_year(s),
+            _ => return Err(DecodeError::mismatch::<MYSQL_TIME>(column)),
+        };
+        parsed.ok_or_else(|| DecodeError::invalid(column, value))
+    }
+}
+
+/// Decodes a temporal column into `MYSQL_TIME` and converts it to `T`,
+/// reporting zero dates and out of range values as decode errors.
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub(crate) fn convert_time<T>(column: &Column, value: Option<&[u8]>) -> Result<T, DecodeError>
+    where T: ::std::convert::TryFrom<MYSQL_TIME, Error = datetime::TimeConversionError>
+{
+    let time = MYSQL_TIME::from_sql_text(column, value)?;
+    T::try_from(time).map_err(|e| DecodeError::new(column, DecodeErrorKind::Invalid(e.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use super::*;
+    use {BINARY_CHARSET_NR, UNSIGNED_FLAG};
+
+    const UTF8MB4: u32 = 45;
+
+    fn column(column_type: enum_field_types) -> Column {
+        Column::for_test(column_type, 0, BINARY_CHARSET_NR)
+    }
+
+    fn unsigned(column_type: enum_field_types) -> Column {
+        Column::for_test(column_type, UNSIGNED_FLAG, BINARY_CHARSET_NR)
+    }
+
+    fn decode<T: FromSqlText>(column: &Column, value: &[u8]) -> Result<T, DecodeError> {
+        T::from_sql_text(column, Some(value))
+    }
+
+    fn error_kind<T: FromSqlText + Debug>(column: &Column, value: &[u8]) -> DecodeErrorKind {
+        decode::<T>(column, value).unwrap_err().kind
+    }
+
+    fn out_of_range<T>() -> DecodeErrorKind {
+        DecodeErrorKind::OutOfRange { target: type_name::<T>() }
+    }
+
+    fn mismatch<T>() -> DecodeErrorKind {
+        DecodeErrorKind::TypeMismatch { target: type_name::<T>() }
+    }
+
+    #[test]
+    fn signed_integers_are_range_checked() {
+        let tiny = column(MYSQL_TYPE_TINY);
+        assert_eq!(decode::<i8>(&tiny, b"-128").unwrap(), -128);
+        assert_eq!(decode::<i8>(&tiny, b"127").unwrap(), 127);
+        assert_eq!(error_kind::<i8>(&tiny, b"128"), out_of_range::<i8>());
+        assert_eq!(error_kind::<u8>(&tiny, b"-1"), out_of_range::<u8>());
+        let big = column(MYSQL_TYPE_LONGLONG);
+        assert_eq!(decode::<i64>(&big, b"-9223372036854775808").unwrap(), i64::MIN);
+        assert_eq!(decode::<u64>(&big, b"9223372036854775807").unwrap(), i64::MAX as u64);
+        assert!(matches!(error_kind::<i64>(&big, b"9223372036854775808"),
+                         DecodeErrorKind::Invalid(_)));
+    }
+
+    #[test]
+    fn unsigned_integers_are_range_checked() {
+        let big = unsigned(MYSQL_TYPE_LONGLONG);
+        assert_eq!(decode::<u64>(&big, b"18446744073709551615").unwrap(), u64::MAX);
+        assert_eq!(error_kind::<i64>(&big, b"18446744073709551615"), out_of_range::<i64>());
+        assert!(matches!(error_kind::<u64>(&big, b"-1"), DecodeErrorKind::Invalid(_)));
+        let short = unsigned(MYSQL_TYPE_SHORT);
+        assert_eq!(decode::<u16>(&short, b"65535").unwrap(), u16::MAX);
+        assert_eq!(error_kind::<i16>(&short, b"65535"), out_of_range::<i16>());
+    }
+
+    #[test]
+    fn integers_reject_other_text_and_types() {
+        let long = column(MYSQL_TYPE_LONG);
+        for value in &[&b""[..], b"12a", b" 1", b"1.0"] {
+            assert!(matches!(error_kind::<i32>(&long, value), DecodeErrorKind::Invalid(_)));
+        }
+        assert_eq!(error_kind::<i32>(&column(MYSQL_TYPE_VARCHAR), b"1"), mismatch::<i32>());
+        assert_eq!(error_kind::<i32>(&column(MYSQL_TYPE_DOUBLE), b"1"), mismatch::<i32>());
+        assert_eq!(i32::from_sql_text(&long, None).unwrap_err().kind,
+                   DecodeErrorKind::UnexpectedNull);
+    }
+
+    #[test]
+    fn bit_columns_are_big_endian_integers() {
+        let bit = column(MYSQL_TYPE_BIT);
+        assert_eq!(decode::<u16>(&bit, b"\x01\x02").unwrap(), 0x0102);
+        assert_eq!(decode::<u64>(&bit, b"").unwrap(), 0);
+        assert_eq!(decode::<u64>(&bit, &[0xff; 8]).unwrap(), u64::MAX);
+        assert_eq!(error_kind::<u64>(&bit, &[0; 9]), out_of_range::<u64>());
+        assert_eq!(error_kind::<i8>(&bit, b"\xff"), out_of_range::<i8>());
+    }
+
+    #[test]
+    fn booleans() {
+        let tiny = column(MYSQL_TYPE_TINY);
+        assert!(!decode::<bool>(&tiny, b"0").unwrap());
+        assert!(decode::<bool>(&tiny, b"1").unwrap());
+        assert!(decode::<bool>(&tiny, b"-2").unwrap());
+        assert!(decode::<bool>(&column(MYSQL_TYPE_BIT), b"\x01").unwrap());
+        assert_eq!(error_kind::<bool>(&column(MYSQL_TYPE_LONG), b"1"), mismatch::<bool>());
+    }
+
+    #[test]
+    fn strings_reject_binary_columns() {
+        let text = Column::for_test(MYSQL_TYPE_VAR_STRING, 0, UTF8MB4);
+        assert_eq!(decode::<String>(&text, "caf\u{e9}".as_bytes()).unwrap(), "caf\u{e9}");
+        assert!(decode::<String>(&text, b"\xff").is_err());
+        for &ty in &[MYSQL_TYPE_VAR_STRING, MYSQL_TYPE_STRING, MYSQL_TYPE_BLOB, MYSQL_TYPE_BIT,
+                     MYSQL_TYPE_GEOMETRY] {
+            assert_eq!(error_kind::<String>(&column(ty), b"a"), mismatch::<String>());
+        }
+        assert_eq!(decode::<String>(&column(MYSQL_TYPE_JSON), b"{}").unwrap(), "{}");
+        assert_eq!(decode::<String>(&column(MYSQL_TYPE_LONG), b"42").unwrap(), "42");
+        assert_eq!(decode::<Vec<u8>>(&column(MYSQL_TYPE_BLOB), b"\xff").unwrap(), b"\xff");
+    }
+
+    #[test]
+    fn decimal_strings() {
+        let decimal = column(MYSQL_TYPE_NEWDECIMAL);
+        for value in &["0", "-12.50", "123456789012345678901234567890.5"] {
+            assert_eq!(decode::<DecimalString>(&decimal, value.as_bytes()).unwrap().0, *value);
+        }
+        assert_eq!(decode::<DecimalString>(&column(MYSQL_TYPE_LONG), b"-7").unwrap().0, "-7");
+        for value in &["", "-", "--5", "---1.0", "+5", "1.", ".5", "1e5", "1.2.3"] {
+            assert!(matches!(error_kind::<DecimalString>(&decimal, value.as_bytes()),
+                             DecodeErrorKind::Invalid(_)),
+                    "{:?}",
+                    value);
+        }
+        assert_eq!(error_kind::<DecimalString>(&column(MYSQL_TYPE_DOUBLE), b"1"),
+                   mismatch::<DecimalString>());
+    }
+}
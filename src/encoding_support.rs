@@ -0,0 +1,149 @@
+//! Decoding of text cells according to the character set of their column.
+
+use std::str;
+
+use encoding_rs::*;
+
+use charset::{Collation, mariadb_collation, mysql_collation};
+use column::Column;
+use decode::{DecodeError, DecodeErrorKind};
+
+/// The encoding of text in the collation `id`, or `None` for `binary`, for
+/// unknown ids and for character sets that `encoding_rs` does not support,
+/// such as `utf32` and `swe7`.
+///
+/// MySQL's `latin1` is Windows-1252, and `tis620` is read as its superset
+/// Windows-874. The MySQL and MariaDB collation ids are both recognized;
+/// they only overlap where they agree.
+pub fn encoding_for_collation(id: u32) -> Option<&'static Encoding> {
+    collation(id).and_then(|collation| charset_encoding(collation.charset))
+}
+
+fn collation(id: u32) -> Option<Collation> {
+    mysql_collation(id).or_else(|| mariadb_collation(id))
+}
+
+fn charset_encoding(charset: &str) -> Option<&'static Encoding> {
+    let encoding = match charset {
+        "utf8mb3" | "utf8mb4" => UTF_8,
+        "ascii" | "latin1" => WINDOWS_1252,
+        "latin2" => ISO_8859_2,
+        "latin5" => WINDOWS_1254,
+        "latin7" => ISO_8859_13,
+        "cp1250" => WINDOWS_1250,
+        "cp1251" => WINDOWS_1251,
+        "cp1256" => WINDOWS_1256,
+        "cp1257" => WINDOWS_1257,
+        "cp866" => IBM866,
+        "greek" => ISO_8859_7,
+        "hebrew" => ISO_8859_8,
+        "koi8r" => KOI8_R,
+        "koi8u" => KOI8_U,
+        "macroman" => MACINTOSH,
+        "tis620" => WINDOWS_874,
+        "big5" => BIG5,
+        "euckr" => EUC_KR,
+        "gb2312" | "gbk" => GBK,
+        "gb18030" => GB18030,
+        "sjis" | "cp932" => SHIFT_JIS,
+        "ujis" | "eucjpms" => EUC_JP,
+        // UCS-2 is the BMP subset of UTF-16.
+        "ucs2" | "utf16" => UTF_16BE,
+        "utf16le" => UTF_16LE,
+        _ => return None,
+    };
+    Some(encoding)
+}
+
+/// Decodes a cell of a text column, failing on bytes that are not valid in
+/// the column's character set rather than replacing them.
+pub(crate) fn decode_text(column: &Column, value: &[u8]) -> Result<String, DecodeError> {
+    let id = column.charset_id();
+    let unsupported = || DecodeError::new(column, DecodeErrorKind::UnsupportedCharset(id));
+    let charset = collation(id).ok_or_else(unsupported)?.charset;
+    let encoding = charset_encoding(charset).ok_or_else(unsupported)?;
+    let invalid = |valid_up_to| {
+        DecodeError::new(column,
+                         DecodeErrorKind::InvalidText {
+                             charset,
+                             valid_up_to,
+                         })
+    };
+    if encoding == UTF_8 {
+        return str::from_utf8(value)
+            .map(str::to_owned)
+            .map_err(|e| invalid(e.valid_up_to()));
+    }
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let capacity = decoder.max_utf8_buffer_length_without_replacement(value.len())
+        .expect("cell too large to decode");
+    let mut decoded = String::with_capacity(capacity);
+    match decoder.decode_to_string_without_replacement(value, &mut decoded, true) {
+        (DecoderResult::InputEmpty, _) => Ok(decoded),
+        // `extra` bytes were read past the malformed sequence.
+        (DecoderResult::Malformed(bad, extra), read) => {
+            Err(invalid(read - bad as usize - extra as usize))
+        }
+        (DecoderResult::OutputFull, _) => unreachable!("buffer sized for the whole cell"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use super::*;
+    use column::Column;
+    use decode::DecodeErrorKind;
+    use MYSQL_FIELD;
+
+    fn decode(charset_id: u32, value: &[u8]) -> Result<String, DecodeError> {
+        let mut field: MYSQL_FIELD = unsafe { mem::zeroed() };
+        field.charsetnr = charset_id;
+        let column = unsafe { &Column::slice(&field, 1)[0] };
+        decode_text(column, value)
+    }
+
+    fn valid_up_to(charset_id: u32, value: &[u8]) -> usize {
+        match decode(charset_id, value).unwrap_err().kind {
+            DecodeErrorKind::InvalidText { valid_up_to, .. } => valid_up_to,
+            kind => panic!("unexpected error {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn latin1_is_windows_1252() {
+        assert_eq!(decode(8, b"caf\xe9 \x80").unwrap(), "caf\u{e9} \u{20ac}");
+    }
+
+    #[test]
+    fn cp1251() {
+        assert_eq!(decode(51, b"\xcf\xf0\xe8\xe2\xe5\xf2").unwrap(),
+                   "\u{41f}\u{440}\u{438}\u{432}\u{435}\u{442}");
+    }
+
+    #[test]
+    fn sjis() {
+        assert_eq!(decode(13, b"a\x82\xa0").unwrap(), "a\u{3042}");
+        assert_eq!(valid_up_to(13, b"ab\x82"), 2);
+        assert_eq!(valid_up_to(13, b"a\x82 b"), 1);
+    }
+
+    #[test]
+    fn error_offset_excludes_bytes_read_past_the_malformed_sequence() {
+        // GB18030 reads two bytes past the lone lead byte before failing.
+        assert_eq!(valid_up_to(248, b"a\x81\x30\x81\x20"), 1);
+    }
+
+    #[test]
+    fn utf8_is_checked_strictly() {
+        assert_eq!(decode(45, "\u{e9}".as_bytes()).unwrap(), "\u{e9}");
+        assert_eq!(valid_up_to(45, b"ab\xc3"), 2);
+    }
+
+    #[test]
+    fn unknown_collations_are_unsupported() {
+        assert_eq!(decode(9999, b"a").unwrap_err().kind,
+                   DecodeErrorKind::UnsupportedCharset(9999));
+    }
+}
@@ -0,0 +1,405 @@
+//! Decoding and binding of `ENUM`, `SET` and `BIT` values.
+//!
+//! `ENUM` and `SET` columns are reported as string columns with `ENUM_FLAG`
+//! or `SET_FLAG` set, and their values are sent as text in both protocols.
+//! `BIT(n)` values arrive as `ceil(n / 8)` big-endian bytes, and can already
+//! be decoded into any unsigned integer wide enough to hold them.
+
+use std::any::type_name;
+use std::collections::BTreeSet;
+
+use column::Column;
+use decode::{DecodeError, DecodeErrorKind, FromSqlText};
+use params::{Param, ToSqlParam};
+
+/// Rust enums that map to the members of an `ENUM` column.
+///
+/// This can be implemented with the `sql_enum!` macro or by a derive macro
+/// that generates string conversions.
+pub trait SqlEnum: Sized {
+    /// Looks up the variant for a member name, as returned by the server.
+    fn from_sql_name(name: &str) -> Option<Self>;
+
+    /// The member name of the variant.
+    fn sql_name(&self) -> &'static str;
+}
+
+/// Types that map to the members of a `SET` column.
+pub trait SqlSet: Sized {
+    /// Builds a value from the member names of a cell, returning `None` if
+    /// one of them is unknown.
+    fn from_sql_members<'a, I: Iterator<Item = &'a str>>(members: I) -> Option<Self>;
+
+    /// The member names contained in the value.
+    fn sql_members(&self) -> Vec<&str>;
+}
+
+/// Implements `SqlEnum` for a fieldless enum, mapping each variant to a
+/// member name.
+///
+/// ```ignore
+/// enum Size { Small, Large }
+///
+/// sql_enum!(Size { Small => "small", Large => "large" });
+/// ```
+#[macro_export]
+macro_rules! sql_enum {
+    ($ty:ident { $($variant:ident => $name:expr),* $(,)* }) => {
+        impl $crate::enums::SqlEnum for $ty {
+            fn from_sql_name(name: &str) -> Option<Self> {
+                $(if name == $name {
+                    return Some($ty::$variant);
+                })*
+                None
+            }
+
+            fn sql_name(&self) -> &'static str {
+                match *self {
+                    $($ty::$variant => $name,)*
+                }
+            }
+        }
+    }
+}
+
+/// An `ENUM` value decoded into and bound from a `SqlEnum`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Enum<T>(pub T);
+
+/// A `SET` value decoded into and bound from a `SqlSet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Set<T>(pub T);
+
+fn flagged_text<'a, T>(column: &Column,
+                       value: Option<&'a [u8]>,
+                       flagged: bool)
+                       -> Result<&'a str, DecodeError> {
+    let value = value.ok_or_else(|| DecodeError::new(column, DecodeErrorKind::UnexpectedNull))?;
+    if !flagged {
+        let target = type_name::<T>();
+        return Err(DecodeError::new(column, DecodeErrorKind::TypeMismatch { target }));
+    }
+    ::std::str::from_utf8(value).map_err(|_| invalid(column, value))
+}
+
+fn invalid(column: &Column, value: &[u8]) -> DecodeError {
+    let value = String::from_utf8_lossy(value).into_owned();
+    DecodeError::new(column, DecodeErrorKind::Invalid(value))
+}
+
+/// Splits a `SET` cell into its members. The empty set is sent as an empty
+/// string.
+fn set_members(text: &str) -> impl Iterator<Item = &str> {
+    text.split(',').filter(|member| !member.is_empty())
+}
+
+/// Requires `ENUM_FLAG`. The empty string that the server stores for invalid
+/// values in non-strict mode is reported as `Invalid` unless `T` maps it.
+impl<T: SqlEnum> FromSqlText for Enum<T> {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        let text = flagged_text::<T>(column, value, column.is_enum())?;
+        T::from_sql_name(text).map(Enum).ok_or_else(|| invalid(column, text.as_bytes()))
+    }
+}
+
+impl<T: SqlEnum> ToSqlParam for Enum<T> {
+    fn to_sql_param(&self) -> Param {
+        self.0.sql_name().to_sql_param()
+    }
+}
+
+/// Requires `SET_FLAG`.
+impl<T: SqlSet> FromSqlText for Set<T> {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        let text = flagged_text::<T>(column, value, column.is_set())?;
+        T::from_sql_members(set_members(text))
+            .map(Set)
+            .ok_or_else(|| invalid(column, text.as_bytes()))
+    }
+}
+
+impl<T: SqlSet> ToSqlParam for Set<T> {
+    fn to_sql_param(&self) -> Param {
+        self.0.sql_members().join(",").to_sql_param()
+    }
+}
+
+impl SqlSet for BTreeSet<String> {
+    fn from_sql_members<'a, I: Iterator<Item = &'a str>>(members: I) -> Option<Self> {
+        Some(members.map(str::to_owned).collect())
+    }
+
+    fn sql_members(&self) -> Vec<&str> {
+        self.iter().map(String::as_str).collect()
+    }
+}
+
+/// Requires `SET_FLAG`.
+impl FromSqlText for BTreeSet<String> {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        Set::from_sql_text(column, value).map(|set| set.0)
+    }
+}
+
+impl ToSqlParam for BTreeSet<String> {
+    fn to_sql_param(&self) -> Param {
+        self.iter().map(String::as_str).collect::<Vec<_>>().join(",").to_sql_param()
+    }
+}
+
+#[cfg(feature = "bitflags")]
+pub use self::flags::SetFlags;
+
+#[cfg(feature = "bitflags")]
+mod flags {
+    use bitflags::Flags;
+
+    use column::Column;
+    use decode::{DecodeError, FromSqlText};
+    use params::{Param, ToSqlParam};
+    use super::{SqlSet, Set};
+
+    /// A `SET` value decoded into and bound from a `bitflags` type.
+    ///
+    /// Members are matched to the flag names case-insensitively, as the
+    /// server compares them, so `const READ` maps to the member `read`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+    pub struct SetFlags<F>(pub F);
+
+    impl<F: Flags> SqlSet for SetFlags<F> {
+        fn from_sql_members<'a, I: Iterator<Item = &'a str>>(members: I) -> Option<Self> {
+            let mut flags = F::empty();
+            for member in members {
+                let flag = F::FLAGS.iter().find(|f| f.name().eq_ignore_ascii_case(member))?;
+                flags.insert(F::from_bits_retain(flag.value().bits()));
+            }
+            Some(SetFlags(flags))
+        }
+
+        fn sql_members(&self) -> Vec<&str> {
+            self.0.iter_names().map(|(name, _)| name).collect()
+        }
+    }
+
+    /// Requires `SET_FLAG`.
+    impl<F: Flags> FromSqlText for SetFlags<F> {
+        fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+            Set::from_sql_text(column, value).map(|set| set.0)
+        }
+    }
+
+    /// Bits that do not correspond to a named flag are not sent.
+    impl<F: Flags> ToSqlParam for SetFlags<F> {
+        fn to_sql_param(&self) -> Param {
+            self.sql_members().join(",").to_sql_param()
+        }
+    }
+}
+
+#[cfg(feature = "bitvec")]
+mod bits {
+    use bitvec::order::Msb0;
+    use bitvec::vec::BitVec;
+
+    use column::Column;
+    use decode::{DecodeError, DecodeErrorKind, FromSqlText};
+    use enum_field_types::{self, MYSQL_TYPE_BIT};
+    use params::{Param, ToSqlParam};
+
+    /// Decodes a `BIT(n)` column into exactly `n` bits, most significant bit
+    /// first.
+    impl FromSqlText for BitVec<u8, Msb0> {
+        fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+            let value =
+                value.ok_or_else(|| DecodeError::new(column, DecodeErrorKind::UnexpectedNull))?;
+            if column.column_type() != MYSQL_TYPE_BIT {
+                let target = ::std::any::type_name::<Self>();
+                return Err(DecodeError::new(column, DecodeErrorKind::TypeMismatch { target }));
+            }
+            let bits = BitVec::<u8, Msb0>::from_slice(value);
+            let width = column.display_length() as usize;
+            if width > bits.len() || bits[..bits.len() - width].any() {
+                return Err(DecodeError::out_of_range::<Self>(column));
+            }
+            Ok(bits[bits.len() - width..].to_bitvec())
+        }
+    }
+
+    /// Sent as a binary string, left-padded with zero bits to whole bytes,
+    /// which the server assigns to `BIT` columns bit for bit.
+    impl ToSqlParam for BitVec<u8, Msb0> {
+        fn to_sql_param(&self) -> Param {
+            let mut padded = BitVec::<u8, Msb0>::repeat(false, (8 - self.len() % 8) % 8);
+            padded.extend_from_bitslice(self);
+            Param::bytes(enum_field_types::MYSQL_TYPE_BLOB, padded.into_vec())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use super::*;
+    use binder::Value;
+    use enum_field_types::{self, MYSQL_TYPE_STRING};
+    use {ENUM_FLAG, SET_FLAG};
+
+    const UTF8MB4: u32 = 45;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Size {
+        Small,
+        Large,
+    }
+
+    sql_enum!(Size { Small => "small", Large => "large", });
+
+    fn column(flags: u32) -> Column {
+        Column::for_test(MYSQL_TYPE_STRING, flags, UTF8MB4)
+    }
+
+    fn error_kind<T>(column: &Column, value: Option<&[u8]>) -> DecodeErrorKind
+        where T: FromSqlText + Debug
+    {
+        T::from_sql_text(column, value).unwrap_err().kind
+    }
+
+    fn sent_bytes(param: Param, buffer_type: enum_field_types) -> Vec<u8> {
+        assert_eq!(param.buffer_type(), buffer_type);
+        match param.value() {
+            Value::Bytes(bytes) => bytes.to_vec(),
+            other => panic!("unexpected value {:?}", other),
+        }
+    }
+
+    fn members(text: &str) -> Vec<&str> {
+        set_members(text).collect()
+    }
+
+    #[test]
+    fn set_cells_are_split_into_members() {
+        assert!(members("").is_empty());
+        assert_eq!(members("a"), ["a"]);
+        assert_eq!(members("a,b"), ["a", "b"]);
+        assert_eq!(members("a,b,"), ["a", "b"]);
+        assert_eq!(members(",a,,b"), ["a", "b"]);
+    }
+
+    #[test]
+    fn sql_enum_maps_variants_to_members() {
+        assert_eq!(Size::from_sql_name("small"), Some(Size::Small));
+        assert_eq!(Size::from_sql_name("large"), Some(Size::Large));
+        assert_eq!(Size::from_sql_name("Small"), None);
+        assert_eq!(Size::from_sql_name(""), None);
+        assert_eq!(Size::Large.sql_name(), "large");
+    }
+
+    #[test]
+    fn enums() {
+        let enum_column = column(ENUM_FLAG);
+        assert_eq!(Enum::<Size>::from_sql_text(&enum_column, Some(b"large")),
+                   Ok(Enum(Size::Large)));
+        assert_eq!(error_kind::<Enum<Size>>(&enum_column, Some(b"")),
+                   DecodeErrorKind::Invalid(String::new()));
+        assert_eq!(error_kind::<Enum<Size>>(&enum_column, None),
+                   DecodeErrorKind::UnexpectedNull);
+        assert!(matches!(error_kind::<Enum<Size>>(&column(0), Some(b"large")),
+                         DecodeErrorKind::TypeMismatch { .. }));
+        assert_eq!(sent_bytes(Enum(Size::Small).to_sql_param(), MYSQL_TYPE_STRING), b"small");
+    }
+
+    #[test]
+    fn sets() {
+        let set_column = column(SET_FLAG);
+        let set = BTreeSet::from_sql_text(&set_column, Some(b"b,a")).unwrap();
+        assert_eq!(set, ["a", "b"].iter().map(|s| s.to_string()).collect());
+        assert_eq!(sent_bytes(set.to_sql_param(), MYSQL_TYPE_STRING), b"a,b");
+        assert_eq!(sent_bytes(Set(set).to_sql_param(), MYSQL_TYPE_STRING), b"a,b");
+        let empty = BTreeSet::from_sql_text(&set_column, Some(b"")).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(sent_bytes(empty.to_sql_param(), MYSQL_TYPE_STRING), b"");
+        assert!(matches!(error_kind::<BTreeSet<String>>(&column(ENUM_FLAG), Some(b"a")),
+                         DecodeErrorKind::TypeMismatch { .. }));
+    }
+
+    #[cfg(feature = "bitflags")]
+    mod flags {
+        use bitflags::bitflags;
+
+        use super::*;
+
+        bitflags! {
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            struct Access: u8 {
+                const READ = 1;
+                const WRITE = 2;
+            }
+        }
+
+        #[test]
+        fn members_match_flag_names_case_insensitively() {
+            let set_column = column(SET_FLAG);
+            assert_eq!(SetFlags::from_sql_text(&set_column, Some(b"read,Write")),
+                       Ok(SetFlags(Access::READ | Access::WRITE)));
+            assert_eq!(SetFlags::from_sql_text(&set_column, Some(b"")),
+                       Ok(SetFlags(Access::empty())));
+            assert_eq!(error_kind::<SetFlags<Access>>(&set_column, Some(b"read,execute")),
+                       DecodeErrorKind::Invalid("read,execute".into()));
+        }
+
+        #[test]
+        fn unnamed_bits_are_not_sent() {
+            let flags = SetFlags(Access::from_bits_retain(0x83));
+            assert_eq!(sent_bytes(flags.to_sql_param(), MYSQL_TYPE_STRING), b"READ,WRITE");
+        }
+    }
+
+    #[cfg(feature = "bitvec")]
+    mod bits {
+        use bitvec::bitvec;
+        use bitvec::order::Msb0;
+        use bitvec::vec::BitVec;
+
+        use super::*;
+        use enum_field_types::{MYSQL_TYPE_BIT, MYSQL_TYPE_BLOB};
+        use BINARY_CHARSET_NR;
+
+        fn bit_column(width: u64) -> Column {
+            Column::for_test(MYSQL_TYPE_BIT, 0, BINARY_CHARSET_NR).with_length(width, 0)
+        }
+
+        fn decode(width: u64, value: &[u8]) -> Result<BitVec<u8, Msb0>, DecodeError> {
+            BitVec::from_sql_text(&bit_column(width), Some(value))
+        }
+
+        #[test]
+        fn values_are_cut_to_the_column_width() {
+            assert_eq!(decode(3, &[0b101]), Ok(bitvec![u8, Msb0; 1, 0, 1]));
+            assert_eq!(decode(10, &[0b10, 0xff]).unwrap().len(), 10);
+            assert_eq!(decode(8, &[0x80]), Ok(bitvec![u8, Msb0; 1, 0, 0, 0, 0, 0, 0, 0]));
+            assert_eq!(decode(0, &[]), Ok(BitVec::new()));
+        }
+
+        #[test]
+        fn values_wider_than_the_column_are_rejected() {
+            let out_of_range = DecodeErrorKind::OutOfRange {
+                target: ::std::any::type_name::<BitVec<u8, Msb0>>(),
+            };
+            assert_eq!(decode(3, &[0b1000]).unwrap_err().kind, out_of_range);
+            assert_eq!(decode(10, &[0b100, 0]).unwrap_err().kind, out_of_range);
+            assert_eq!(decode(10, &[0xff]).unwrap_err().kind, out_of_range);
+            assert!(matches!(error_kind::<BitVec<u8, Msb0>>(&column(0), Some(b"\x01")),
+                             DecodeErrorKind::TypeMismatch { .. }));
+        }
+
+        #[test]
+        fn params_are_padded_to_whole_bytes() {
+            let sent = |bits: BitVec<u8, Msb0>| sent_bytes(bits.to_sql_param(), MYSQL_TYPE_BLOB);
+            assert_eq!(sent(bitvec![u8, Msb0; 1, 0, 1]), [0b101]);
+            assert_eq!(sent(bitvec![u8, Msb0; 1; 8]), [0xff]);
+            assert_eq!(sent(bitvec![u8, Msb0; 1; 9]), [0b1, 0xff]);
+            assert_eq!(sent(BitVec::new()), [] as [u8; 0]);
+        }
+    }
+}
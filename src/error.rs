@@ -0,0 +1,188 @@
+use std::error::Error as StdError;
+use std::ffi::{CStr, NulError};
+use std::fmt;
+use std::io;
+use std::os::raw::c_char;
+use std::result;
+
+use decode::DecodeError;
+use interpolate::InterpolateError;
+use {MYSQL, MYSQL_STMT, mysql_errno, mysql_error, mysql_sqlstate, mysql_stmt_errno,
+     mysql_stmt_error, mysql_stmt_sqlstate};
+
+pub type Result<T> = result::Result<T, Error>;
+
+/// Errors returned by the safe layer of this crate.
+#[derive(Debug)]
+pub enum Error {
+    /// An error reported by libmysqlclient or the server.
+    Mysql(MysqlError),
+    /// A string passed to the client library contained an interior nul byte.
+    InteriorNul(NulError),
+    /// `mysql_init` could not allocate a connection handle.
+    OutOfMemory,
+    /// A value could not be decoded into the requested Rust type.
+    Decode(DecodeError),
+    /// The number of bound parameters does not match the number of
+    /// placeholders in the prepared statement.
+    ParamCount { expected: usize, actual: usize },
+    /// Reading or writing streamed data failed.
+    Io(io::Error),
+    /// A transaction was started while another one was open, which would
+    /// implicitly commit it.
+    TransactionInProgress,
+    /// The transaction was already ended by the server, either implicitly
+    /// committed by a statement such as DDL or rolled back after an error.
+    TransactionEnded,
+    /// Placeholders could not be replaced with literals.
+    Interpolate(InterpolateError),
+    /// No pooled connection became available within the checkout timeout.
+    PoolTimeout,
+    /// The settings passed to `Pool::new` contradict each other.
+    InvalidPoolOptions(&'static str),
+    /// A nonblocking call was dropped before it completed, leaving the
+    /// connection in the middle of it.
+    Poisoned,
+    /// A string could not be escaped for the connection's character set.
+    Escape(&'static str),
+    /// The linked client library does not lay out the `MYSQL` handle as
+    /// `mysql_nonblocking` expects from libmysqlclient 8.0.
+    IncompatibleLibrary,
+    /// The worker thread of a `ThreadedConnection` stopped, such as after a
+    /// panic in the client library, before it replied to a call.
+    WorkerGone,
+}
+
+/// The error number, SQLSTATE and message of a failed client library call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MysqlError {
+    pub code: u32,
+    pub sqlstate: String,
+    pub message: String,
+}
+
+impl MysqlError {
+    /// Reads the last error reported on a connection handle.
+    ///
+    /// # Safety
+    ///
+    /// `mysql` must be a valid handle returned by `mysql_init`.
+    pub unsafe fn from_connection(mysql: *mut MYSQL) -> Self {
+        MysqlError {
+            code: mysql_errno(mysql) as u32,
+            sqlstate: lossy_string(mysql_sqlstate(mysql)),
+            message: lossy_string(mysql_error(mysql)),
+        }
+    }
+
+    /// Reads the last error reported on a statement handle.
+    ///
+    /// # Safety
+    ///
+    /// `stmt` must be a valid handle returned by `mysql_stmt_init`.
+    pub unsafe fn from_statement(stmt: *mut MYSQL_STMT) -> Self {
+        MysqlError {
+            code: mysql_stmt_errno(stmt) as u32,
+            sqlstate: lossy_string(mysql_stmt_sqlstate(stmt)),
+            message: lossy_string(mysql_stmt_error(stmt)),
+        }
+    }
+}
+
+unsafe fn lossy_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
+
+impl fmt::Display for MysqlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.code, self.sqlstate, self.message)
+    }
+}
+
+impl StdError for MysqlError {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Mysql(ref e) => e.fmt(f),
+            Error::InteriorNul(ref e) => e.fmt(f),
+            Error::OutOfMemory => f.write_str("libmysqlclient could not allocate a handle"),
+            Error::Decode(ref e) => e.fmt(f),
+            Error::Io(ref e) => e.fmt(f),
+            Error::ParamCount { expected, actual } => {
+                write!(f, "statement expects {} parameters, but {} were bound", expected, actual)
+            }
+            Error::Interpolate(ref e) => e.fmt(f),
+            Error::PoolTimeout => f.write_str("timed out waiting for a pooled connection"),
+            Error::InvalidPoolOptions(reason) => write!(f, "invalid pool options: {}", reason),
+            Error::Poisoned => {
+                f.write_str("the connection was left in the middle of a cancelled call")
+            }
+            Error::Escape(reason) => write!(f, "cannot escape string: {}", reason),
+            Error::IncompatibleLibrary => {
+                f.write_str("the linked client library does not use the MySQL 8.0 MYSQL layout")
+            }
+            Error::WorkerGone => f.write_str("the connection's worker thread has stopped"),
+            Error::TransactionInProgress => f.write_str("a transaction is already in progress"),
+            Error::TransactionEnded => {
+                f.write_str("the transaction was implicitly committed or rolled back")
+            }
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            Error::Mysql(ref e) => Some(e),
+            Error::InteriorNul(ref e) => Some(e),
+            Error::OutOfMemory |
+            Error::ParamCount { .. } |
+            Error::TransactionInProgress |
+            Error::TransactionEnded |
+            Error::PoolTimeout |
+            Error::InvalidPoolOptions(_) |
+            Error::Poisoned |
+            Error::Escape(_) |
+            Error::IncompatibleLibrary |
+            Error::WorkerGone => None,
+            Error::Decode(ref e) => Some(e),
+            Error::Io(ref e) => Some(e),
+            Error::Interpolate(ref e) => Some(e),
+        }
+    }
+}
+
+impl From<MysqlError> for Error {
+    fn from(e: MysqlError) -> Self {
+        Error::Mysql(e)
+    }
+}
+
+impl From<NulError> for Error {
+    fn from(e: NulError) -> Self {
+        Error::InteriorNul(e)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Self {
+        Error::Decode(e)
+    }
+}
+
+impl From<InterpolateError> for Error {
+    fn from(e: InterpolateError) -> Self {
+        Error::Interpolate(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
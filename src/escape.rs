@@ -0,0 +1,182 @@
+//! Escaping of string literals and identifiers for SQL text.
+//!
+//! Prefer prepared statements where possible. These functions are for SQL
+//! that has to be assembled as text, such as identifiers or statements that
+//! cannot be prepared.
+
+use std::os::raw::{c_char, c_ulong};
+
+use connection::Connection;
+use error::{Error, Result};
+use {SERVER_STATUS_NO_BACKSLASH_ESCAPES, mysql_hex_string, mysql_real_escape_string_quote};
+
+/// The quote character a value will be enclosed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quote {
+    Single,
+    Double,
+    Backtick,
+}
+
+impl Quote {
+    fn as_char(&self) -> char {
+        match *self {
+            Quote::Single => '\'',
+            Quote::Double => '"',
+            Quote::Backtick => '`',
+        }
+    }
+}
+
+impl Connection {
+    /// Whether the server reported `NO_BACKSLASH_ESCAPES` in its last reply,
+    /// in which case quotes are escaped by doubling them.
+    pub fn no_backslash_escapes(&self) -> bool {
+        self.server_status() & SERVER_STATUS_NO_BACKSLASH_ESCAPES != 0
+    }
+}
+
+/// Escapes `s` with `mysql_real_escape_string_quote` so that it can be
+/// placed between two `quote` characters.
+///
+/// Backticks and, under `NO_BACKSLASH_ESCAPES`, the quote character are
+/// doubled; otherwise special characters are escaped with a backslash.
+///
+/// The client library reads `s` in the connection's character set. In
+/// character sets such as `gbk` or `sjis`, a UTF-8 string may end in what
+/// looks like an incomplete multibyte character, which gets a backslash
+/// inserted before it. That fails with `Error::Escape`, since the result
+/// would not be UTF-8.
+pub fn escape_str(conn: &Connection, s: &str, quote: Quote) -> Result<String> {
+    let mut buf = vec![0u8; s.len() * 2 + 1];
+    let len = unsafe {
+        mysql_real_escape_string_quote(conn.as_ptr(),
+                                       buf.as_mut_ptr() as *mut c_char,
+                                       s.as_ptr() as *const c_char,
+                                       s.len() as c_ulong,
+                                       quote.as_char() as c_char)
+    };
+    escaped_string(buf, len)
+}
+
+/// Takes the output of `mysql_real_escape_string_quote`, which reports
+/// failure as `(ulong)-1`.
+fn escaped_string(mut buf: Vec<u8>, len: c_ulong) -> Result<String> {
+    if len as u64 >= buf.len() as u64 {
+        return Err(Error::Escape("the escaped string does not fit its buffer"));
+    }
+    buf.truncate(len as usize);
+    String::from_utf8(buf)
+        .map_err(|_| Error::Escape("the string is not valid in the connection's character set"))
+}
+
+/// Escapes `s` and encloses it in single quotes, as a string literal.
+pub fn quote_str(conn: &Connection, s: &str) -> Result<String> {
+    Ok(format!("'{}'", escape_str(conn, s, Quote::Single)?))
+}
+
+/// Encloses an identifier in backticks, doubling any backticks inside it.
+pub fn quote_identifier(conn: &Connection, name: &str) -> Result<String> {
+    Ok(format!("`{}`", escape_str(conn, name, Quote::Backtick)?))
+}
+
+/// Formats `bytes` as a hexadecimal literal such as `X'C3A9'`, using
+/// `mysql_hex_string`. The literal does not depend on the character set or
+/// SQL mode of the connection.
+pub fn hex_literal(bytes: &[u8]) -> String {
+    let mut buf = vec![0u8; bytes.len() * 2 + 1];
+    let len = unsafe {
+        mysql_hex_string(buf.as_mut_ptr() as *mut c_char,
+                         bytes.as_ptr() as *const c_char,
+                         bytes.len() as c_ulong)
+    };
+    buf.truncate(len as usize);
+    let hex = String::from_utf8(buf).expect("mysql_hex_string produced non-ASCII output");
+    format!("X'{}'", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+    use connection::ConnectOptions;
+    use mysql_set_character_set;
+
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    /// Connects with the client library's defaults and option files. Tests
+    /// that need a server are ignored; run them with
+    /// `cargo test -- --ignored`.
+    fn connect(charset: &str) -> Connection {
+        let conn = Connection::connect(&ConnectOptions::new()).expect("cannot reach a server");
+        let charset = CString::new(charset).unwrap();
+        assert_eq!(unsafe { mysql_set_character_set(conn.as_ptr(), charset.as_ptr()) }, 0);
+        conn
+    }
+
+    fn assert_escape_error(result: Result<String>) {
+        match result {
+            Err(Error::Escape(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn failures_are_errors() {
+        assert_escape_error(escaped_string(vec![0; 3], c_ulong::MAX));
+        assert_escape_error(escaped_string(vec![0; 3], 3));
+        assert_escape_error(escaped_string(b"\xe4\xb8\\\xad\0".to_vec(), 4));
+        assert_eq!(escaped_string(b"it\\'s\0\0".to_vec(), 5).unwrap(), r"it\'s");
+    }
+
+    #[test]
+    fn hex_literal_matches_manual_formatting() {
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..1_000 {
+            let bytes: Vec<u8> = (0..rng.next() % 64).map(|_| rng.next() as u8).collect();
+            let expected: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            assert_eq!(hex_literal(&bytes), format!("X'{}'", expected));
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn special_characters_are_escaped() {
+        let conn = connect("utf8mb4");
+        assert_eq!(escape_str(&conn, "a'b\"c\\d\0\n\r\x1a中", Quote::Single).unwrap(),
+                   r#"a\'b\"c\\d\0\n\r\Z中"#);
+        assert_eq!(quote_str(&conn, "it's").unwrap(), r"'it\'s'");
+        assert_eq!(quote_identifier(&conn, "a`b").unwrap(), "`a``b`");
+        assert_eq!(quote_identifier(&conn, "").unwrap(), "``");
+    }
+
+    #[test]
+    #[ignore]
+    fn quotes_are_doubled_without_backslash_escapes() {
+        let mut conn = connect("utf8mb4");
+        conn.query("SET SESSION sql_mode = 'NO_BACKSLASH_ESCAPES'").unwrap();
+        assert!(conn.no_backslash_escapes());
+        assert_eq!(escape_str(&conn, r"it's \", Quote::Single).unwrap(), r"it''s \");
+        assert_eq!(escape_str(&conn, "a\"b'", Quote::Double).unwrap(), "a\"\"b'");
+    }
+
+    #[test]
+    #[ignore]
+    fn strings_that_are_not_valid_gbk_are_rejected() {
+        let conn = connect("gbk");
+        // E4 B8 is read as one gbk character and the trailing AD as an
+        // incomplete one, which gets a backslash.
+        assert_escape_error(escape_str(&conn, "中", Quote::Single));
+        assert_eq!(escape_str(&conn, "it's", Quote::Single).unwrap(), r"it\'s");
+    }
+}
@@ -0,0 +1,478 @@
+//! Conversions between spatial values and `geo_types::Geometry`.
+//!
+//! MySQL and MariaDB store geometries as a 4-byte little-endian SRID
+//! followed by the geometry in Well-Known Binary. Cells arrive in that format
+//! with both the text and binary protocols, and parameters are sent in it as
+//! `MYSQL_TYPE_BLOB`.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use geo_types::{Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint,
+                MultiPolygon, Point, Polygon};
+
+use column::Column;
+use decode::{DecodeError, DecodeErrorKind, FromSqlText};
+use enum_field_types::{self, MYSQL_TYPE_GEOMETRY};
+use params::{Param, ToSqlParam};
+
+const WKB_POINT: u32 = 1;
+const WKB_LINESTRING: u32 = 2;
+const WKB_POLYGON: u32 = 3;
+const WKB_MULTIPOINT: u32 = 4;
+const WKB_MULTILINESTRING: u32 = 5;
+const WKB_MULTIPOLYGON: u32 = 6;
+const WKB_GEOMETRYCOLLECTION: u32 = 7;
+
+/// Collections nested deeper than this are rejected rather than risking a
+/// stack overflow on malformed input.
+const MAX_NESTING: usize = 64;
+
+/// Why a value is not a valid MySQL geometry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryError {
+    /// The value ended in the middle of a geometry.
+    UnexpectedEof,
+    /// The WKB byte order marker was neither 0 nor 1.
+    InvalidByteOrder(u8),
+    /// The WKB geometry type is unknown, or not allowed at this position.
+    InvalidType(u32),
+    /// Geometry collections were nested more than 64 levels deep.
+    TooDeep,
+    /// Bytes were left over after the geometry.
+    TrailingBytes,
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GeometryError::UnexpectedEof => f.write_str("unexpected end of geometry"),
+            GeometryError::InvalidByteOrder(b) => write!(f, "invalid WKB byte order {}", b),
+            GeometryError::InvalidType(t) => write!(f, "invalid WKB geometry type {}", t),
+            GeometryError::TooDeep => f.write_str("geometry collections nested too deeply"),
+            GeometryError::TrailingBytes => f.write_str("trailing bytes after geometry"),
+        }
+    }
+}
+
+impl StdError for GeometryError {}
+
+/// A geometry together with its spatial reference system identifier.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SridGeometry {
+    pub srid: u32,
+    pub geometry: Geometry<f64>,
+}
+
+impl SridGeometry {
+    pub fn new<G: Into<Geometry<f64>>>(srid: u32, geometry: G) -> Self {
+        SridGeometry {
+            srid,
+            geometry: geometry.into(),
+        }
+    }
+
+    /// Parses the internal format used by MySQL and MariaDB.
+    pub fn from_mysql_bytes(bytes: &[u8]) -> Result<Self, GeometryError> {
+        let mut reader = Reader {
+            bytes,
+            little_endian: true,
+        };
+        let srid = reader.u32()?;
+        let geometry = reader.geometry(0)?;
+        if !reader.bytes.is_empty() {
+            return Err(GeometryError::TrailingBytes);
+        }
+        Ok(SridGeometry { srid, geometry })
+    }
+
+    /// Encodes the geometry in the internal format used by MySQL and
+    /// MariaDB, always as little-endian WKB.
+    ///
+    /// `Line`, `Rect` and `Triangle` have no WKB counterpart and are written
+    /// as a `LINESTRING` and `POLYGON`s respectively.
+    pub fn to_mysql_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.srid.to_le_bytes());
+        write_geometry(&mut out, &self.geometry);
+        out
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], GeometryError> {
+        if self.bytes.len() < n {
+            return Err(GeometryError::UnexpectedEof);
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, GeometryError> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(if self.little_endian {
+            u32::from_le_bytes(buf)
+        } else {
+            u32::from_be_bytes(buf)
+        })
+    }
+
+    fn f64(&mut self) -> Result<f64, GeometryError> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(if self.little_endian {
+            f64::from_le_bytes(buf)
+        } else {
+            f64::from_be_bytes(buf)
+        })
+    }
+
+    /// Reads an element count, rejecting counts that cannot possibly fit in
+    /// the remaining input before anything is allocated for them.
+    fn count(&mut self, min_element_size: usize) -> Result<usize, GeometryError> {
+        let n = self.u32()? as usize;
+        if n.saturating_mul(min_element_size) > self.bytes.len() {
+            return Err(GeometryError::UnexpectedEof);
+        }
+        Ok(n)
+    }
+
+    /// Reads a WKB header, returning the geometry type.
+    fn header(&mut self) -> Result<u32, GeometryError> {
+        self.little_endian = match self.take(1)?[0] {
+            0 => false,
+            1 => true,
+            b => return Err(GeometryError::InvalidByteOrder(b)),
+        };
+        self.u32()
+    }
+
+    fn coord(&mut self) -> Result<Coord<f64>, GeometryError> {
+        Ok(Coord {
+            x: self.f64()?,
+            y: self.f64()?,
+        })
+    }
+
+    fn line_string(&mut self) -> Result<LineString<f64>, GeometryError> {
+        let n = self.count(16)?;
+        (0..n).map(|_| self.coord()).collect::<Result<Vec<_>, _>>().map(LineString)
+    }
+
+    fn polygon(&mut self) -> Result<Polygon<f64>, GeometryError> {
+        let n = self.count(4)?;
+        let mut rings = (0..n).map(|_| self.line_string()).collect::<Result<Vec<_>, _>>()?;
+        if rings.is_empty() {
+            return Ok(Polygon::new(LineString(Vec::new()), Vec::new()));
+        }
+        let exterior = rings.remove(0);
+        Ok(Polygon::new(exterior, rings))
+    }
+
+    /// Reads a nested geometry that must be of type `expected`.
+    fn member(&mut self, expected: u32) -> Result<(), GeometryError> {
+        match self.header()? {
+            ty if ty == expected => Ok(()),
+            ty => Err(GeometryError::InvalidType(ty)),
+        }
+    }
+
+    fn geometry(&mut self, depth: usize) -> Result<Geometry<f64>, GeometryError> {
+        if depth > MAX_NESTING {
+            return Err(GeometryError::TooDeep);
+        }
+        let geometry = match self.header()? {
+            WKB_POINT => Point(self.coord()?).into(),
+            WKB_LINESTRING => self.line_string()?.into(),
+            WKB_POLYGON => self.polygon()?.into(),
+            WKB_MULTIPOINT => {
+                let n = self.count(21)?;
+                let points = (0..n)
+                    .map(|_| {
+                        self.member(WKB_POINT)?;
+                        self.coord().map(Point)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                MultiPoint(points).into()
+            }
+            WKB_MULTILINESTRING => {
+                let n = self.count(9)?;
+                let lines = (0..n)
+                    .map(|_| {
+                        self.member(WKB_LINESTRING)?;
+                        self.line_string()
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                MultiLineString(lines).into()
+            }
+            WKB_MULTIPOLYGON => {
+                let n = self.count(9)?;
+                let polygons = (0..n)
+                    .map(|_| {
+                        self.member(WKB_POLYGON)?;
+                        self.polygon()
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                MultiPolygon(polygons).into()
+            }
+            WKB_GEOMETRYCOLLECTION => {
+                let n = self.count(5)?;
+                let members = (0..n)
+                    .map(|_| self.geometry(depth + 1))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Geometry::GeometryCollection(GeometryCollection(members))
+            }
+            ty => return Err(GeometryError::InvalidType(ty)),
+        };
+        Ok(geometry)
+    }
+}
+
+fn write_header(out: &mut Vec<u8>, ty: u32) {
+    out.push(1);
+    out.extend_from_slice(&ty.to_le_bytes());
+}
+
+fn write_count(out: &mut Vec<u8>, n: usize) {
+    out.extend_from_slice(&(n as u32).to_le_bytes());
+}
+
+fn write_coord(out: &mut Vec<u8>, coord: Coord<f64>) {
+    out.extend_from_slice(&coord.x.to_le_bytes());
+    out.extend_from_slice(&coord.y.to_le_bytes());
+}
+
+fn write_line_string(out: &mut Vec<u8>, line: &LineString<f64>) {
+    write_count(out, line.0.len());
+    for &coord in &line.0 {
+        write_coord(out, coord);
+    }
+}
+
+fn write_polygon(out: &mut Vec<u8>, polygon: &Polygon<f64>) {
+    if polygon.exterior().0.is_empty() && polygon.interiors().is_empty() {
+        return write_count(out, 0);
+    }
+    write_count(out, 1 + polygon.interiors().len());
+    write_line_string(out, polygon.exterior());
+    for ring in polygon.interiors() {
+        write_line_string(out, ring);
+    }
+}
+
+fn write_geometry(out: &mut Vec<u8>, geometry: &Geometry<f64>) {
+    match *geometry {
+        Geometry::Point(point) => {
+            write_header(out, WKB_POINT);
+            write_coord(out, point.0);
+        }
+        Geometry::Line(line) => {
+            write_header(out, WKB_LINESTRING);
+            write_line_string(out, &LineString(vec![line.start, line.end]));
+        }
+        Geometry::LineString(ref line) => {
+            write_header(out, WKB_LINESTRING);
+            write_line_string(out, line);
+        }
+        Geometry::Polygon(ref polygon) => {
+            write_header(out, WKB_POLYGON);
+            write_polygon(out, polygon);
+        }
+        Geometry::MultiPoint(ref points) => {
+            write_header(out, WKB_MULTIPOINT);
+            write_count(out, points.0.len());
+            for point in &points.0 {
+                write_header(out, WKB_POINT);
+                write_coord(out, point.0);
+            }
+        }
+        Geometry::MultiLineString(ref lines) => {
+            write_header(out, WKB_MULTILINESTRING);
+            write_count(out, lines.0.len());
+            for line in &lines.0 {
+                write_header(out, WKB_LINESTRING);
+                write_line_string(out, line);
+            }
+        }
+        Geometry::MultiPolygon(ref polygons) => {
+            write_header(out, WKB_MULTIPOLYGON);
+            write_count(out, polygons.0.len());
+            for polygon in &polygons.0 {
+                write_header(out, WKB_POLYGON);
+                write_polygon(out, polygon);
+            }
+        }
+        Geometry::GeometryCollection(ref members) => {
+            write_header(out, WKB_GEOMETRYCOLLECTION);
+            write_count(out, members.0.len());
+            for member in &members.0 {
+                write_geometry(out, member);
+            }
+        }
+        Geometry::Rect(rect) => {
+            write_header(out, WKB_POLYGON);
+            write_polygon(out, &rect.to_polygon());
+        }
+        Geometry::Triangle(triangle) => {
+            write_header(out, WKB_POLYGON);
+            write_polygon(out, &triangle.to_polygon());
+        }
+    }
+}
+
+impl FromSqlText for SridGeometry {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        let value = value.ok_or_else(|| DecodeError::new(column, DecodeErrorKind::UnexpectedNull))?;
+        if column.column_type() != MYSQL_TYPE_GEOMETRY {
+            let target = ::std::any::type_name::<SridGeometry>();
+            return Err(DecodeError::new(column, DecodeErrorKind::TypeMismatch { target }));
+        }
+        SridGeometry::from_mysql_bytes(value)
+            .map_err(|e| DecodeError::new(column, DecodeErrorKind::Invalid(e.to_string())))
+    }
+}
+
+/// Decodes the geometry, discarding its SRID.
+impl FromSqlText for Geometry<f64> {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        SridGeometry::from_sql_text(column, value).map(|g| g.geometry)
+    }
+}
+
+impl ToSqlParam for SridGeometry {
+    fn to_sql_param(&self) -> Param {
+        Param::bytes(enum_field_types::MYSQL_TYPE_BLOB, self.to_mysql_bytes())
+    }
+}
+
+/// Sent with SRID 0.
+impl ToSqlParam for Geometry<f64> {
+    fn to_sql_param(&self) -> Param {
+        SridGeometry::new(0, self.clone()).to_sql_param()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(geometry: Geometry<f64>) {
+        let value = SridGeometry::new(4326, geometry);
+        let bytes = value.to_mysql_bytes();
+        assert_eq!(SridGeometry::from_mysql_bytes(&bytes), Ok(value));
+    }
+
+    fn ring(coords: &[(f64, f64)]) -> LineString<f64> {
+        coords.iter().map(|&(x, y)| Coord { x, y }).collect()
+    }
+
+    /// `depth` geometry collections, each holding the next, around a point.
+    fn nested(depth: usize) -> Vec<u8> {
+        let mut bytes = 0u32.to_le_bytes().to_vec();
+        for _ in 0..depth {
+            write_header(&mut bytes, WKB_GEOMETRYCOLLECTION);
+            write_count(&mut bytes, 1);
+        }
+        write_header(&mut bytes, WKB_POINT);
+        write_coord(&mut bytes, Coord { x: 1.0, y: 2.0 });
+        bytes
+    }
+
+    #[test]
+    fn point_with_srid_prefix() {
+        let bytes = SridGeometry::new(4326, Point::new(1.0, -2.5)).to_mysql_bytes();
+        let mut expected = vec![0xe6, 0x10, 0, 0, 1, 1, 0, 0, 0];
+        expected.extend_from_slice(&1.0f64.to_le_bytes());
+        expected.extend_from_slice(&(-2.5f64).to_le_bytes());
+        assert_eq!(bytes, expected);
+        assert_eq!(SridGeometry::from_mysql_bytes(&bytes),
+                   Ok(SridGeometry::new(4326, Point::new(1.0, -2.5))));
+    }
+
+    #[test]
+    fn big_endian_wkb() {
+        let mut bytes = vec![0, 0, 0, 0, 0, 0, 0, 0, 1];
+        bytes.extend_from_slice(&3.0f64.to_be_bytes());
+        bytes.extend_from_slice(&4.0f64.to_be_bytes());
+        assert_eq!(SridGeometry::from_mysql_bytes(&bytes),
+                   Ok(SridGeometry::new(0, Point::new(3.0, 4.0))));
+    }
+
+    #[test]
+    fn round_trips() {
+        let square = ring(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 0.0)]);
+        let hole = ring(&[(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 1.0)]);
+        let polygon = Polygon::new(square.clone(), vec![hole]);
+        round_trip(Point::new(1.5, 2.5).into());
+        round_trip(square.clone().into());
+        round_trip(polygon.clone().into());
+        round_trip(Polygon::new(LineString(Vec::new()), Vec::new()).into());
+        round_trip(MultiPoint(vec![Point::new(0.0, 1.0), Point::new(2.0, 3.0)]).into());
+        round_trip(MultiLineString(vec![square.clone(), LineString(Vec::new())]).into());
+        round_trip(MultiPolygon(vec![polygon.clone()]).into());
+        let inner = GeometryCollection(vec![square.into()]);
+        let outer = GeometryCollection(vec![Point::new(0.0, 0.0).into(),
+                                            Geometry::GeometryCollection(inner),
+                                            polygon.into()]);
+        round_trip(Geometry::GeometryCollection(outer));
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let expected = (0..MAX_NESTING).fold(Geometry::from(Point::new(1.0, 2.0)), |g, _| {
+            Geometry::GeometryCollection(GeometryCollection(vec![g]))
+        });
+        assert_eq!(SridGeometry::from_mysql_bytes(&nested(MAX_NESTING)),
+                   Ok(SridGeometry::new(0, expected)));
+        assert_eq!(SridGeometry::from_mysql_bytes(&nested(MAX_NESTING + 1)),
+                   Err(GeometryError::TooDeep));
+    }
+
+    #[test]
+    fn truncated_input() {
+        let polygon = Polygon::new(ring(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)]),
+                                   Vec::new());
+        let bytes = SridGeometry::new(0, MultiPolygon(vec![polygon])).to_mysql_bytes();
+        for len in 0..bytes.len() {
+            assert_eq!(SridGeometry::from_mysql_bytes(&bytes[..len]),
+                       Err(GeometryError::UnexpectedEof),
+                       "{} bytes",
+                       len);
+        }
+    }
+
+    #[test]
+    fn huge_count_is_rejected_before_allocating() {
+        let mut bytes = vec![0, 0, 0, 0];
+        write_header(&mut bytes, WKB_LINESTRING);
+        write_count(&mut bytes, u32::MAX as usize);
+        assert_eq!(SridGeometry::from_mysql_bytes(&bytes), Err(GeometryError::UnexpectedEof));
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(SridGeometry::from_mysql_bytes(&[0, 0, 0, 0, 2, 1, 0, 0, 0]),
+                   Err(GeometryError::InvalidByteOrder(2)));
+        assert_eq!(SridGeometry::from_mysql_bytes(&[0, 0, 0, 0, 1, 8, 0, 0, 0]),
+                   Err(GeometryError::InvalidType(8)));
+
+        let mut bytes = vec![0, 0, 0, 0];
+        write_header(&mut bytes, WKB_MULTIPOINT);
+        write_count(&mut bytes, 1);
+        write_header(&mut bytes, WKB_LINESTRING);
+        write_coord(&mut bytes, Coord { x: 0.0, y: 0.0 });
+        assert_eq!(SridGeometry::from_mysql_bytes(&bytes),
+                   Err(GeometryError::InvalidType(WKB_LINESTRING)));
+
+        let mut bytes = SridGeometry::new(0, Point::new(0.0, 0.0)).to_mysql_bytes();
+        bytes.push(0);
+        assert_eq!(SridGeometry::from_mysql_bytes(&bytes), Err(GeometryError::TrailingBytes));
+    }
+}
@@ -0,0 +1,227 @@
+//! Fields of the `MYSQL` handle that the C API has no getters for.
+//!
+//! The generated bindings describe libmysqlclient 5.7, whose layout is used
+//! by default. `mysql_nonblocking` requires libmysqlclient 8.0.16 or newer,
+//! which dropped fields from `NET` and moved everything after it; the fields
+//! are then read through a description of the 8.0 layout. When
+//! `AsyncConnection` drives MariaDB Connector/C, they are queried with
+//! `mariadb_get_infov` instead.
+
+use std::os::raw::{c_char, c_uint};
+
+use error::Result;
+#[cfg(feature = "mysql_nonblocking")]
+use error::Error;
+use MYSQL;
+#[cfg(not(any(feature = "mysql_nonblocking", feature = "mariadb_nonblocking")))]
+use mysql_status;
+
+/// The host, credentials and socket a connection was opened with.
+pub(crate) struct Endpoint {
+    pub(crate) host: *const c_char,
+    pub(crate) user: *const c_char,
+    pub(crate) passwd: *const c_char,
+    pub(crate) unix_socket: *const c_char,
+    pub(crate) port: c_uint,
+}
+
+/// Checks that the fields of a connected handle can be read with the layout
+/// this module assumes. Only the 8.0 layout needs checking, since the 5.7
+/// one comes from the generated bindings and MariaDB's is never read.
+#[cfg(not(feature = "mysql_nonblocking"))]
+pub(crate) unsafe fn check_layout(_mysql: *mut MYSQL) -> Result<()> {
+    Ok(())
+}
+
+/// Checks that the fields of a connected handle can be read with the layout
+/// this module assumes, comparing them with the C API's getters.
+#[cfg(feature = "mysql_nonblocking")]
+pub(crate) unsafe fn check_layout(mysql: *mut MYSQL) -> Result<()> {
+    if mysql80::matches(mysql) {
+        Ok(())
+    } else {
+        Err(Error::IncompatibleLibrary)
+    }
+}
+
+/// The `SERVER_STATUS_*` flags of the server's last reply.
+#[cfg(not(any(feature = "mysql_nonblocking", feature = "mariadb_nonblocking")))]
+pub(crate) unsafe fn server_status(mysql: *mut MYSQL) -> c_uint {
+    (*mysql).server_status
+}
+
+/// The `SERVER_STATUS_*` flags of the server's last reply.
+#[cfg(feature = "mysql_nonblocking")]
+pub(crate) unsafe fn server_status(mysql: *mut MYSQL) -> c_uint {
+    mysql80::prefix(mysql).server_status
+}
+
+/// The `SERVER_STATUS_*` flags of the server's last reply.
+#[cfg(all(feature = "mariadb_nonblocking", not(feature = "mysql_nonblocking")))]
+pub(crate) unsafe fn server_status(mysql: *mut MYSQL) -> c_uint {
+    use mariadb_nonblocking::{MARIADB_CONNECTION_SERVER_STATUS, mariadb_get_infov};
+
+    let mut status: c_uint = 0;
+    let out = &mut status as *mut c_uint as *mut _;
+    mariadb_get_infov(mysql, MARIADB_CONNECTION_SERVER_STATUS, out);
+    status
+}
+
+/// Whether no result set is waiting to be read, or `None` if the client
+/// library does not expose it.
+#[cfg(not(any(feature = "mysql_nonblocking", feature = "mariadb_nonblocking")))]
+pub(crate) unsafe fn is_ready(mysql: *mut MYSQL) -> Option<bool> {
+    Some((*mysql).status == mysql_status::MYSQL_STATUS_READY)
+}
+
+/// Whether no result set is waiting to be read, or `None` if the client
+/// library does not expose it. `status` follows `st_mysql_options`, whose
+/// layout differs between 8.0 releases and in MariaDB.
+#[cfg(any(feature = "mysql_nonblocking", feature = "mariadb_nonblocking"))]
+pub(crate) unsafe fn is_ready(_mysql: *mut MYSQL) -> Option<bool> {
+    None
+}
+
+#[cfg(not(any(feature = "mysql_nonblocking", feature = "mariadb_nonblocking")))]
+pub(crate) unsafe fn endpoint(mysql: *mut MYSQL) -> Endpoint {
+    let raw = &*mysql;
+    Endpoint {
+        host: raw.host,
+        user: raw.user,
+        passwd: raw.passwd,
+        unix_socket: raw.unix_socket,
+        port: raw.port,
+    }
+}
+
+#[cfg(feature = "mysql_nonblocking")]
+pub(crate) unsafe fn endpoint(mysql: *mut MYSQL) -> Endpoint {
+    let raw = mysql80::prefix(mysql);
+    Endpoint {
+        host: raw.host,
+        user: raw.user,
+        passwd: raw.passwd,
+        unix_socket: raw.unix_socket,
+        port: raw.port,
+    }
+}
+
+/// MariaDB Connector/C does not expose the password, which is left null.
+#[cfg(all(feature = "mariadb_nonblocking", not(feature = "mysql_nonblocking")))]
+pub(crate) unsafe fn endpoint(mysql: *mut MYSQL) -> Endpoint {
+    use std::ptr;
+
+    use mariadb_nonblocking::{MARIADB_CONNECTION_HOST, MARIADB_CONNECTION_PORT,
+                              MARIADB_CONNECTION_UNIX_SOCKET, MARIADB_CONNECTION_USER,
+                              mariadb_get_infov};
+
+    let string = |value| {
+        let mut s: *const c_char = ptr::null();
+        mariadb_get_infov(mysql, value, &mut s as *mut *const c_char as *mut _);
+        s
+    };
+    let mut port: c_uint = 0;
+    mariadb_get_infov(mysql, MARIADB_CONNECTION_PORT, &mut port as *mut c_uint as *mut _);
+    Endpoint {
+        host: string(MARIADB_CONNECTION_HOST),
+        user: string(MARIADB_CONNECTION_USER),
+        passwd: ptr::null(),
+        unix_socket: string(MARIADB_CONNECTION_UNIX_SOCKET),
+        port,
+    }
+}
+
+/// The start of `MYSQL` and `NET` as declared by libmysqlclient 8.0.
+#[cfg(feature = "mysql_nonblocking")]
+mod mysql80 {
+    use std::os::raw::{c_char, c_int, c_uchar, c_uint, c_ulong, c_void};
+    use std::ptr;
+
+    use {MYSQL, mysql_get_host_info, mysql_get_server_info, mysql_thread_id,
+         mysql_warning_count};
+
+    #[cfg(unix)]
+    type Socket = c_int;
+    #[cfg(windows)]
+    type Socket = ::SOCKET;
+
+    #[repr(C)]
+    struct Net {
+        vio: *mut c_void,
+        buff: *mut c_uchar,
+        buff_end: *mut c_uchar,
+        write_pos: *mut c_uchar,
+        read_pos: *mut c_uchar,
+        fd: Socket,
+        remain_in_buf: c_ulong,
+        length: c_ulong,
+        buf_length: c_ulong,
+        where_b: c_ulong,
+        max_packet: c_ulong,
+        max_packet_size: c_ulong,
+        pkt_nr: c_uint,
+        compress_pkt_nr: c_uint,
+        write_timeout: c_uint,
+        read_timeout: c_uint,
+        retry_count: c_uint,
+        fcntl: c_int,
+        return_status: *mut c_uint,
+        reading_or_writing: c_uchar,
+        save_char: c_uchar,
+        compress: bool,
+        last_errno: c_uint,
+        error: c_uchar,
+        last_error: [c_char; 512],
+        sqlstate: [c_char; 6],
+        extension: *mut c_void,
+    }
+
+    /// `MYSQL` up to `warning_count`, which precedes `st_mysql_options`.
+    #[repr(C)]
+    pub(super) struct Prefix {
+        net: Net,
+        connector_fd: *mut c_uchar,
+        pub(super) host: *mut c_char,
+        pub(super) user: *mut c_char,
+        pub(super) passwd: *mut c_char,
+        pub(super) unix_socket: *mut c_char,
+        server_version: *mut c_char,
+        host_info: *mut c_char,
+        info: *mut c_char,
+        db: *mut c_char,
+        charset: *mut c_void,
+        fields: *mut c_void,
+        field_alloc: *mut c_void,
+        affected_rows: u64,
+        insert_id: u64,
+        extra_info: u64,
+        thread_id: c_ulong,
+        packet_length: c_ulong,
+        pub(super) port: c_uint,
+        client_flag: c_ulong,
+        server_capabilities: c_ulong,
+        protocol_version: c_uint,
+        field_count: c_uint,
+        pub(super) server_status: c_uint,
+        server_language: c_uint,
+        warning_count: c_uint,
+    }
+
+    /// Reads the start of `mysql` with the 8.0 layout.
+    ///
+    /// The layout is checked with `matches` when a connection is opened;
+    /// it belongs to the linked library, so it holds for every handle.
+    pub(super) unsafe fn prefix<'a>(mysql: *mut MYSQL) -> &'a Prefix {
+        &*(mysql as *const Prefix)
+    }
+
+    /// Whether the fields of a connected handle that the C API has getters
+    /// for are where the 8.0 layout puts them.
+    pub(super) unsafe fn matches(mysql: *mut MYSQL) -> bool {
+        let prefix = prefix(mysql);
+        prefix.thread_id == mysql_thread_id(mysql) &&
+        prefix.warning_count == mysql_warning_count(mysql) &&
+        ptr::eq(prefix.server_version, mysql_get_server_info(mysql)) &&
+        ptr::eq(prefix.host_info, mysql_get_host_info(mysql))
+    }
+}
@@ -0,0 +1,424 @@
+//! Client-side substitution of placeholders with SQL literals.
+//!
+//! This is meant for statements that cannot be prepared, or for logging.
+//! Placeholders inside string literals, quoted identifiers and comments are
+//! left alone, except in `/*! ... */` and `/*M! ... */` comments, which the
+//! server executes. Values are rendered from their `Param`: strings are
+//! escaped for the connection, binary strings are written as hexadecimal
+//! literals and temporal values as quoted strings.
+
+use std::error::Error as StdError;
+use std::fmt::{self, Write};
+use std::str;
+
+use binder::Value;
+use connection::Connection;
+use decimal;
+use enum_field_types::*;
+use error::{Error, Result};
+use escape::{hex_literal, quote_str};
+use params::{Param, Params, ToSqlParam};
+
+/// Why a statement could not be interpolated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterpolateError {
+    /// No value was given for a `:name` placeholder.
+    MissingName(String),
+    /// A float was NaN or infinite, which SQL cannot represent.
+    NonFiniteFloat,
+}
+
+impl fmt::Display for InterpolateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InterpolateError::MissingName(ref name) => {
+                write!(f, "no value given for placeholder :{}", name)
+            }
+            InterpolateError::NonFiniteFloat => f.write_str("cannot render a non-finite float"),
+        }
+    }
+}
+
+impl StdError for InterpolateError {}
+
+/// Parameters for `:name` placeholders.
+#[derive(Debug, Clone, Default)]
+pub struct NamedParams {
+    params: Vec<(String, Param)>,
+}
+
+impl NamedParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a value for `:name`, replacing any earlier value for it.
+    pub fn bind<S: Into<String>, T: ToSqlParam>(mut self, name: S, value: T) -> Self {
+        let name = name.into();
+        let param = value.to_sql_param();
+        match self.params.iter_mut().find(|p| p.0 == name) {
+            Some(existing) => existing.1 = param,
+            None => self.params.push((name, param)),
+        }
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Param> {
+        self.params.iter().find(|p| p.0 == name).map(|p| &p.1)
+    }
+}
+
+/// Replaces each `?` in `sql` with the next value of `params`.
+///
+/// Fails with `Error::ParamCount` unless there is exactly one value per
+/// placeholder.
+pub fn interpolate(conn: &Connection, sql: &str, params: &Params) -> Result<String> {
+    interpolate_with(sql, params, conn.no_backslash_escapes(), &|s| quote_str(conn, s))
+}
+
+/// Replaces each `:name` in `sql` with the value bound for `name`.
+///
+/// A name may be used several times. Values whose name does not appear are
+/// ignored.
+pub fn interpolate_named(conn: &Connection, sql: &str, params: &NamedParams) -> Result<String> {
+    interpolate_named_with(sql, params, conn.no_backslash_escapes(), &|s| quote_str(conn, s))
+}
+
+/// Quotes a string as a literal for the connection.
+type QuoteStr<'a> = &'a dyn Fn(&str) -> Result<String>;
+
+fn interpolate_with(sql: &str,
+                    params: &Params,
+                    no_backslash_escapes: bool,
+                    quote: QuoteStr)
+                    -> Result<String> {
+    let mut count = 0;
+    let out = substitute(sql, no_backslash_escapes, false, |_, out| {
+        count += 1;
+        match params.get(count - 1) {
+            Some(param) => render(param, out, quote),
+            None => Ok(()),
+        }
+    })?;
+    if count != params.len() {
+        return Err(Error::ParamCount {
+            expected: count,
+            actual: params.len(),
+        });
+    }
+    Ok(out)
+}
+
+fn interpolate_named_with(sql: &str,
+                          params: &NamedParams,
+                          no_backslash_escapes: bool,
+                          quote: QuoteStr)
+                          -> Result<String> {
+    substitute(sql, no_backslash_escapes, true, |name, out| {
+        match params.get(name) {
+            Some(param) => render(param, out, quote),
+            None => Err(InterpolateError::MissingName(name.to_owned()).into()),
+        }
+    })
+}
+
+/// Appends `param` as a SQL literal.
+fn render(param: &Param, out: &mut String, quote: QuoteStr) -> Result<()> {
+    match param.value() {
+        Value::Null => out.push_str("NULL"),
+        Value::Int(n) => write!(out, "{}", n).unwrap(),
+        Value::UInt(n) => write!(out, "{}", n).unwrap(),
+        // The exponent makes the server read the literal as a double rather
+        // than as an exact `DECIMAL`.
+        Value::Float(f) if f.is_finite() => write!(out, "{:e}", f).unwrap(),
+        Value::Double(f) if f.is_finite() => write!(out, "{:e}", f).unwrap(),
+        Value::Float(_) | Value::Double(_) => return Err(InterpolateError::NonFiniteFloat.into()),
+        Value::Time(time) => write!(out, "'{}'", time).unwrap(),
+        Value::Bytes(bytes) => {
+            match (param.buffer_type(), str::from_utf8(bytes)) {
+                (MYSQL_TYPE_DECIMAL, Ok(s)) | (MYSQL_TYPE_NEWDECIMAL, Ok(s))
+                    if decimal::split_plain(s).is_some() => out.push_str(s),
+                (MYSQL_TYPE_STRING, Ok(s)) |
+                (MYSQL_TYPE_VAR_STRING, Ok(s)) |
+                (MYSQL_TYPE_VARCHAR, Ok(s)) |
+                (MYSQL_TYPE_JSON, Ok(s)) |
+                (MYSQL_TYPE_ENUM, Ok(s)) |
+                (MYSQL_TYPE_SET, Ok(s)) => out.push_str(&quote(s)?),
+                _ => out.push_str(&hex_literal(bytes)),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_name_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_'
+}
+
+fn is_name_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Copies `sql` while calling `replace` for each placeholder outside of
+/// literals and comments. Positional placeholders are passed as `""`.
+fn substitute<F>(sql: &str, no_backslash_escapes: bool, named: bool, mut replace: F)
+                 -> Result<String>
+    where F: FnMut(&str, &mut String) -> Result<()>
+{
+    let bytes = sql.as_bytes();
+    let mut out = String::with_capacity(sql.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ b'\'' | quote @ b'"' | quote @ b'`' => {
+                let escapes = quote != b'`' && !no_backslash_escapes;
+                i = skip_quoted(bytes, i + 1, quote, escapes);
+            }
+            b'#' => i = skip_line(bytes, i),
+            // `--` only starts a comment when followed by whitespace or a
+            // control character.
+            b'-' if bytes.get(i + 1) == Some(&b'-') &&
+                    bytes.get(i + 2).is_none_or(|&b| b <= b' ') => i = skip_line(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = match executable_comment_body(bytes, i + 2) {
+                    Some(body) => body,
+                    None => skip_block_comment(bytes, i + 2),
+                }
+            }
+            b'?' if !named => {
+                out.push_str(&sql[copied..i]);
+                replace("", &mut out)?;
+                i += 1;
+                copied = i;
+            }
+            b':' if named && bytes.get(i + 1).is_some_and(|&b| is_name_start(b)) &&
+                    (i == 0 || !is_name_char(bytes[i - 1])) => {
+                let end = i + 1 + bytes[i + 1..].iter().take_while(|&&b| is_name_char(b)).count();
+                out.push_str(&sql[copied..i]);
+                replace(&sql[i + 1..end], &mut out)?;
+                i = end;
+                copied = i;
+            }
+            _ => i += 1,
+        }
+    }
+    out.push_str(&sql[copied..]);
+    Ok(out)
+}
+
+/// Returns the index after the closing `quote`, treating doubled quotes and,
+/// if enabled, backslash escapes as part of the literal.
+fn skip_quoted(bytes: &[u8], mut i: usize, quote: u8, escapes: bool) -> usize {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if escapes => i += 2,
+            b if b == quote && bytes.get(i + 1) == Some(&quote) => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn skip_line(bytes: &[u8], i: usize) -> usize {
+    bytes[i..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |n| i + n + 1)
+}
+
+/// Returns the index after the `!` of a `/*! ... */` or `/*M! ... */`
+/// comment, whose contents the server executes, and whose placeholders are
+/// therefore replaced. The closing `*/` is copied like any other text.
+fn executable_comment_body(bytes: &[u8], i: usize) -> Option<usize> {
+    match bytes.get(i) {
+        Some(&b'!') => Some(i + 1),
+        Some(&b'M') if bytes.get(i + 1) == Some(&b'!') => Some(i + 2),
+        _ => None,
+    }
+}
+
+fn skip_block_comment(bytes: &[u8], i: usize) -> usize {
+    bytes[i..].windows(2).position(|w| w == b"*/").map_or(bytes.len(), |n| i + n + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datetime;
+    use decode::DecimalString;
+
+    /// Replaces positional placeholders with `X` and named ones with `<name>`.
+    fn mark(sql: &str, no_backslash_escapes: bool, named: bool) -> String {
+        substitute(sql, no_backslash_escapes, named, |name, out| {
+            if named {
+                write!(out, "<{}>", name).unwrap();
+            } else {
+                out.push('X');
+            }
+            Ok(())
+        }).unwrap()
+    }
+
+    fn positional(sql: &str) -> String {
+        mark(sql, false, false)
+    }
+
+    fn named(sql: &str) -> String {
+        mark(sql, false, true)
+    }
+
+    /// Stands in for `quote_str`, doubling single quotes.
+    fn quote(s: &str) -> Result<String> {
+        Ok(format!("'{}'", s.replace('\'', "''")))
+    }
+
+    fn render_one<T: ToSqlParam>(value: T) -> Result<String> {
+        interpolate_with("?", &Params::new().push(value), false, &quote)
+    }
+
+    #[test]
+    fn quoted_literals_are_skipped() {
+        assert_eq!(positional("SELECT '?', \"?\", ?"), "SELECT '?', \"?\", X");
+        assert_eq!(positional("SELECT 'it''s ?', ?"), "SELECT 'it''s ?', X");
+        assert_eq!(positional(r"SELECT '\'?', ?"), r"SELECT '\'?', X");
+        assert_eq!(positional("SELECT '?"), "SELECT '?");
+    }
+
+    #[test]
+    fn backslashes_do_not_escape_under_no_backslash_escapes() {
+        assert_eq!(mark(r"SELECT '\', ?", true, false), r"SELECT '\', X");
+        assert_eq!(mark(r"SELECT '\', ?", false, false), r"SELECT '\', ?");
+    }
+
+    #[test]
+    fn backtick_identifiers_are_skipped() {
+        assert_eq!(positional("SELECT `a?b`, ?"), "SELECT `a?b`, X");
+        assert_eq!(positional("SELECT `a``?`, ?"), "SELECT `a``?`, X");
+        assert_eq!(positional(r"SELECT `a\`, ?"), r"SELECT `a\`, X");
+    }
+
+    #[test]
+    fn hash_comments_end_at_newline() {
+        assert_eq!(positional("SELECT ? # ?\n, ?"), "SELECT X # ?\n, X");
+        assert_eq!(positional("SELECT 1 # ?"), "SELECT 1 # ?");
+    }
+
+    #[test]
+    fn dash_comments_need_whitespace() {
+        assert_eq!(positional("SELECT ? -- ?\n, ?"), "SELECT X -- ?\n, X");
+        assert_eq!(positional("SELECT 1 --\t?"), "SELECT 1 --\t?");
+        assert_eq!(positional("SELECT 1 --"), "SELECT 1 --");
+        assert_eq!(positional("SELECT 1--?"), "SELECT 1--X");
+    }
+
+    #[test]
+    fn block_comments_are_skipped() {
+        assert_eq!(positional("SELECT /* ? */ ?"), "SELECT /* ? */ X");
+        assert_eq!(positional("SELECT /*/ ? */ ?"), "SELECT /*/ ? */ X");
+        assert_eq!(positional("SELECT 1 /* ?"), "SELECT 1 /* ?");
+    }
+
+    #[test]
+    fn executable_comments_are_code() {
+        assert_eq!(positional("SELECT /*!40001 ? */ 1"), "SELECT /*!40001 X */ 1");
+        assert_eq!(positional("SELECT /*! ? */ ?"), "SELECT /*! X */ X");
+        assert_eq!(positional("SELECT /*M!100200 ? */ ?"), "SELECT /*M!100200 X */ X");
+        assert_eq!(positional("SELECT /*!40001 '?' */ ?"), "SELECT /*!40001 '?' */ X");
+        assert_eq!(positional("SELECT /*M ? */ ?"), "SELECT /*M ? */ X");
+        assert_eq!(named("SELECT /*!40001 :a */ 1"), "SELECT /*!40001 <a> */ 1");
+    }
+
+    #[test]
+    fn named_placeholders() {
+        assert_eq!(named("SELECT :a, :b_1, :a"), "SELECT <a>, <b_1>, <a>");
+        assert_eq!(named("SELECT ?, :1, a:b, ':a'"), "SELECT ?, :1, a:b, ':a'");
+        assert_eq!(named("SELECT ::a"), "SELECT :<a>");
+    }
+
+    #[test]
+    fn positional_mode_ignores_names() {
+        assert_eq!(positional("SELECT :a, ?"), "SELECT :a, X");
+    }
+
+    #[test]
+    fn missing_name() {
+        let err = interpolate_named_with("SELECT :a", &NamedParams::new(), false, &quote)
+            .unwrap_err();
+        match err {
+            Error::Interpolate(InterpolateError::MissingName(name)) => assert_eq!(name, "a"),
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn param_count_must_match() {
+        let params = Params::new().push(1i32);
+        match interpolate_with("SELECT ?, ?", &params, false, &quote) {
+            Err(Error::ParamCount { expected: 2, actual: 1 }) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn floats_render_with_an_exponent() {
+        assert_eq!(render_one(1.5f64).unwrap(), "1.5e0");
+        assert_eq!(render_one(0.25f32).unwrap(), "2.5e-1");
+        assert_eq!(render_one(-2e300f64).unwrap(), "-2e300");
+    }
+
+    #[test]
+    fn non_finite_floats_are_rejected() {
+        for &f in &[f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            match render_one(f) {
+                Err(Error::Interpolate(InterpolateError::NonFiniteFloat)) => {}
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+        assert!(render_one(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn integers_and_null() {
+        assert_eq!(render_one(-7i64).unwrap(), "-7");
+        assert_eq!(render_one(u64::MAX).unwrap(), "18446744073709551615");
+        assert_eq!(render_one(None::<i32>).unwrap(), "NULL");
+    }
+
+    #[test]
+    fn bytes_render_as_hex() {
+        assert_eq!(render_one(vec![0u8, 0xff, b'\'']).unwrap(), "X'00FF27'");
+        assert_eq!(render_one(&b""[..]).unwrap(), "X''");
+    }
+
+    #[test]
+    fn strings_are_quoted_for_the_connection() {
+        assert_eq!(render_one("it's").unwrap(), "'it''s'");
+        let params = NamedParams::new().bind("a", "x").bind("b", 1i32);
+        assert_eq!(interpolate_named_with("SELECT :a, :b, :a", &params, false, &quote).unwrap(),
+                   "SELECT 'x', 1, 'x'");
+    }
+
+    #[test]
+    fn escaping_errors_are_returned() {
+        let fail = |_: &str| Err(Error::Escape("not valid"));
+        match interpolate_with("SELECT ?", &Params::new().push("a"), false, &fail) {
+            Err(Error::Escape(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn temporal_values_are_quoted() {
+        let date = datetime::parse_date("2024-01-31").unwrap();
+        assert_eq!(render_one(date).unwrap(), "'2024-01-31'");
+    }
+
+    #[test]
+    fn decimals_are_checked() {
+        assert_eq!(render_one(DecimalString("-12.50".to_owned())).unwrap(), "-12.50");
+        assert_eq!(render_one(DecimalString("3".to_owned())).unwrap(), "3");
+        for value in &["1.", ".5", "1e5", "--1", ""] {
+            let rendered = render_one(DecimalString(value.to_string())).unwrap();
+            assert_eq!(rendered, hex_literal(value.as_bytes()));
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Conversions between `JSON` values and `serde_json`.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{self, Value};
+
+use column::Column;
+use decode::{DecodeError, DecodeErrorKind, FromSqlText};
+use enum_field_types::{self, *};
+use params::{Param, ToSqlParam};
+
+/// A value stored as JSON, decoded with `Deserialize` and bound with
+/// `Serialize` through `try_to_param`.
+///
+/// There is no `ToSqlParam` impl, since `T`'s `Serialize` implementation may
+/// fail, as for maps with non-string keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Json<T>(pub T);
+
+/// MySQL reports `JSON` columns as `MYSQL_TYPE_JSON` with the binary
+/// character set, while MariaDB stores them as `LONGTEXT` with a `CHECK`
+/// constraint. Text columns are therefore accepted as well, as are the
+/// results of `JSON_*` functions, which MySQL returns as `LONGTEXT`.
+fn json_text<'a, T>(column: &Column, value: Option<&'a [u8]>) -> Result<&'a [u8], DecodeError> {
+    let value = value.ok_or_else(|| DecodeError::new(column, DecodeErrorKind::UnexpectedNull))?;
+    match column.column_type() {
+        MYSQL_TYPE_JSON => Ok(value),
+        MYSQL_TYPE_VARCHAR | MYSQL_TYPE_VAR_STRING | MYSQL_TYPE_STRING | MYSQL_TYPE_TINY_BLOB |
+        MYSQL_TYPE_MEDIUM_BLOB | MYSQL_TYPE_LONG_BLOB | MYSQL_TYPE_BLOB if !column.is_binary() => {
+            Ok(value)
+        }
+        _ => Err(DecodeError::new(column,
+                                  DecodeErrorKind::TypeMismatch {
+                                      target: ::std::any::type_name::<T>(),
+                                  })),
+    }
+}
+
+fn from_json<T: DeserializeOwned>(column: &Column,
+                                  value: Option<&[u8]>)
+                                  -> Result<T, DecodeError> {
+    let text = json_text::<T>(column, value)?;
+    serde_json::from_slice(text)
+        .map_err(|e| DecodeError::new(column, DecodeErrorKind::Invalid(e.to_string())))
+}
+
+impl FromSqlText for Value {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        from_json(column, value)
+    }
+}
+
+/// A SQL `NULL` cell is an error, while a JSON `null` document decodes as
+/// `T`'s representation of `null`. Use `Option<Json<T>>` for nullable
+/// columns.
+impl<T: DeserializeOwned> FromSqlText for Json<T> {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        from_json(column, value).map(Json)
+    }
+}
+
+/// Sent as `MYSQL_TYPE_STRING`, which both MySQL and MariaDB convert to
+/// `JSON` on assignment.
+fn json_param(bytes: Vec<u8>) -> Param {
+    Param::bytes(enum_field_types::MYSQL_TYPE_STRING, bytes)
+}
+
+impl ToSqlParam for Value {
+    fn to_sql_param(&self) -> Param {
+        // A `Value` always has string keys, the only case in which writing
+        // it to a `Vec` could fail.
+        json_param(serde_json::to_vec(self).expect("serde_json::Value is always serializable"))
+    }
+}
+
+impl<T: Serialize> Json<T> {
+    /// Serializes the value into a parameter.
+    pub fn try_to_param(&self) -> Result<Param, serde_json::Error> {
+        serde_json::to_vec(&self.0).map(json_param)
+    }
+}
@@ -1,7 +1,118 @@
 #![allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
+// `c_ulong` is 32 bits wide on Windows, so casts that are no-ops elsewhere
+// are still required there.
+#![allow(clippy::unnecessary_cast)]
+
+#[cfg(feature = "bigdecimal")]
+extern crate bigdecimal;
+#[cfg(feature = "bitflags")]
+extern crate bitflags;
+#[cfg(feature = "bitvec")]
+extern crate bitvec;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "encoding_rs")]
+extern crate encoding_rs;
+#[cfg(feature = "geo")]
+extern crate geo_types;
+#[cfg(feature = "rust_decimal")]
+extern crate rust_decimal;
+#[cfg(feature = "serde_json")]
+extern crate serde;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+#[cfg(feature = "time")]
+extern crate time;
+#[cfg(feature = "tokio")]
+extern crate tokio;
 
 #[cfg(not(windows))]
 include!("bindings_macos.rs");
 
 #[cfg(windows)]
-include!("bindings_windows.rs");
\ No newline at end of file
+include!("bindings_windows.rs");
+
+#[cfg(feature = "bigdecimal")]
+mod bigdecimal_support;
+#[cfg(feature = "chrono")]
+mod chrono_support;
+mod constants;
+#[cfg(feature = "encoding_rs")]
+mod encoding_support;
+mod error;
+#[cfg(feature = "geo")]
+mod geo_support;
+mod handle_info;
+#[cfg(feature = "serde_json")]
+mod json_support;
+mod long_data;
+#[cfg(feature = "mariadb_nonblocking")]
+pub mod mariadb_nonblocking;
+#[cfg(feature = "mysql_nonblocking")]
+pub mod nonblocking;
+#[cfg(feature = "rust_decimal")]
+mod rust_decimal_support;
+#[cfg(feature = "time")]
+mod time_support;
+
+#[cfg(all(unix,
+          feature = "tokio",
+          any(feature = "mysql_nonblocking", feature = "mariadb_nonblocking")))]
+pub mod async_connection;
+pub mod binder;
+pub mod cancel;
+pub mod charset;
+pub mod column;
+pub mod column_reader;
+pub mod connection;
+pub mod datetime;
+pub mod decimal;
+pub mod decode;
+pub mod enums;
+pub mod escape;
+pub mod interpolate;
+pub mod params;
+pub mod pool;
+pub mod result;
+pub mod results;
+pub mod server_version;
+pub mod socket;
+pub mod statement;
+#[cfg(feature = "tokio")]
+pub mod threaded;
+pub mod transaction;
+
+#[cfg(all(unix,
+          feature = "tokio",
+          any(feature = "mysql_nonblocking", feature = "mariadb_nonblocking")))]
+pub use async_connection::AsyncConnection;
+pub use binder::{BoundResult, ResultBinder};
+pub use cancel::CancelHandle;
+pub use charset::{CharsetInfo, Collation, mariadb_collation, mysql_collation};
+pub use column::Column;
+pub use connection::{ConnectOptions, Connection};
+pub use constants::*;
+pub use datetime::TimeConversionError;
+pub use decimal::{DecimalError, DecimalSpec};
+pub use decode::{DecodeError, FromSqlText};
+#[cfg(feature = "encoding_rs")]
+pub use encoding_support::encoding_for_collation;
+pub use enums::{Enum, Set, SqlEnum, SqlSet};
+#[cfg(feature = "bitflags")]
+pub use enums::SetFlags;
+pub use error::{Error, MysqlError, Result};
+pub use escape::{Quote, escape_str, hex_literal, quote_identifier, quote_str};
+pub use interpolate::{InterpolateError, NamedParams, interpolate, interpolate_named};
+#[cfg(feature = "geo")]
+pub use geo_support::{GeometryError, SridGeometry};
+#[cfg(feature = "serde_json")]
+pub use json_support::Json;
+pub use params::{Params, ToSqlParam};
+pub use pool::{Pool, PoolOptions, PooledConnection};
+pub use results::{QueryResult, Results, StatementResult, StatementResults, Status};
+pub use server_version::{Flavor, ServerVersion};
+pub use socket::socket_fd;
+pub use statement::Statement;
+#[cfg(feature = "tokio")]
+pub use threaded::{OwnedRow, RowStream, ThreadedConnection, ThreadedStatement};
+pub use transaction::{AccessMode, IsolationLevel, Transaction, TransactionOptions};
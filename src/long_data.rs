@@ -0,0 +1,72 @@
+//! Streaming of large parameters with `mysql_stmt_send_long_data`.
+
+use std::cmp;
+use std::io::{ErrorKind, Read};
+use std::os::raw::{c_char, c_uint, c_ulong};
+
+use error::Result;
+use statement::Statement;
+use {MYSQL_LONG_DATA_HEADER, mysql_stmt_send_long_data};
+
+/// The largest chunk sent per `COM_STMT_SEND_LONG_DATA` packet.
+const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+impl<'conn> Statement<'conn> {
+    /// Streams the value of parameter `param` from `reader`.
+    ///
+    /// See `send_long_data_with_progress`.
+    pub fn send_long_data<R: Read>(&mut self, param: usize, reader: R) -> Result<u64> {
+        self.send_long_data_with_progress(param, reader, |_| ())
+    }
+
+    /// Streams the value of parameter `param` from `reader` in chunks,
+    /// calling `progress` with the total number of bytes sent after each one.
+    /// Returns the total number of bytes sent.
+    ///
+    /// The parameter should be bound with `Param::long_data`. This must be
+    /// called after `bind_params` and before every `execute`, since the
+    /// server discards long data once the statement has been executed.
+    ///
+    /// Each chunk, together with its `MYSQL_LONG_DATA_HEADER`, fits into the
+    /// client's `max_allowed_packet`. The server's `max_allowed_packet` still
+    /// limits the size of the complete value.
+    pub fn send_long_data_with_progress<R, F>(&mut self,
+                                              param: usize,
+                                              mut reader: R,
+                                              mut progress: F)
+                                              -> Result<u64>
+        where R: Read,
+              F: FnMut(u64)
+    {
+        let header = MYSQL_LONG_DATA_HEADER as u64;
+        // 0 means the option was never set and the library's default applies.
+        let chunk_size = match self.connection().max_allowed_packet()? {
+            0 => MAX_CHUNK_SIZE,
+            max_packet => {
+                cmp::min(max_packet.saturating_sub(header).max(1) as usize, MAX_CHUNK_SIZE)
+            }
+        };
+        let mut buf = vec![0; chunk_size];
+        let mut total = 0;
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let rc = unsafe {
+                mysql_stmt_send_long_data(self.as_ptr(),
+                                          param as c_uint,
+                                          buf.as_ptr() as *const c_char,
+                                          n as c_ulong)
+            };
+            if rc != 0 {
+                return Err(self.last_error());
+            }
+            total += n as u64;
+            progress(total);
+        }
+        Ok(total)
+    }
+}
@@ -0,0 +1,97 @@
+//! Bindings for the nonblocking API of MariaDB Connector/C.
+//!
+//! Each call is split into a `_start` function and a `_cont` function. Both
+//! return 0 once the call has completed, with its result written through the
+//! first argument, or a mask of `MYSQL_WAIT_*` events to wait for before
+//! calling `_cont` with the events that occurred.
+//!
+//! The connection must have `MYSQL_OPT_NONBLOCK` set before it connects.
+//! `mariadb_get_infov` is bound as well, since MariaDB's `MYSQL` struct does
+//! not match the generated one and its fields cannot be read directly.
+//! These functions are not part of the generated bindings and only link
+//! against MariaDB's client library.
+
+use std::os::raw::{c_char, c_int, c_uint, c_ulong, c_void};
+
+use {MYSQL, MYSQL_RES, my_bool};
+
+pub const MYSQL_WAIT_READ: c_int = 1;
+pub const MYSQL_WAIT_WRITE: c_int = 2;
+pub const MYSQL_WAIT_EXCEPT: c_int = 4;
+pub const MYSQL_WAIT_TIMEOUT: c_int = 8;
+
+/// The value of `MYSQL_OPT_NONBLOCK` in MariaDB's `enum mysql_option`, which
+/// has no counterpart in the generated `mysql_option`.
+pub const MYSQL_OPT_NONBLOCK: c_uint = 6000;
+
+/// Values of MariaDB's `enum mariadb_value`, for `mariadb_get_infov`.
+pub const MARIADB_CONNECTION_HOST: c_uint = 10;
+pub const MARIADB_CONNECTION_PORT: c_uint = 12;
+pub const MARIADB_CONNECTION_UNIX_SOCKET: c_uint = 26;
+pub const MARIADB_CONNECTION_USER: c_uint = 27;
+pub const MARIADB_CONNECTION_SERVER_STATUS: c_uint = 30;
+
+#[cfg(unix)]
+pub type my_socket = c_int;
+#[cfg(windows)]
+pub type my_socket = ::SOCKET;
+
+extern "C" {
+    /// `mysql_options` taking the option as a plain integer, for options
+    /// that only exist in MariaDB.
+    #[link_name = "mysql_options"]
+    #[allow(clashing_extern_declarations)]
+    pub fn mysql_options_raw(mysql: *mut MYSQL, option: c_uint, arg: *const c_void) -> c_int;
+
+    /// Writes the connection property `value` through `arg`, returning 0 on
+    /// success.
+    pub fn mariadb_get_infov(mysql: *mut MYSQL, value: c_uint, arg: *mut c_void, ...) -> c_int;
+
+    pub fn mysql_get_socket(mysql: *const MYSQL) -> my_socket;
+    pub fn mysql_get_timeout_value(mysql: *const MYSQL) -> c_uint;
+    pub fn mysql_get_timeout_value_ms(mysql: *const MYSQL) -> c_uint;
+
+    pub fn mysql_real_connect_start(ret: *mut *mut MYSQL,
+                                    mysql: *mut MYSQL,
+                                    host: *const c_char,
+                                    user: *const c_char,
+                                    passwd: *const c_char,
+                                    db: *const c_char,
+                                    port: c_uint,
+                                    unix_socket: *const c_char,
+                                    clientflag: c_ulong)
+                                    -> c_int;
+    pub fn mysql_real_connect_cont(ret: *mut *mut MYSQL, mysql: *mut MYSQL, status: c_int)
+                                   -> c_int;
+    pub fn mysql_real_query_start(ret: *mut c_int,
+                                  mysql: *mut MYSQL,
+                                  query: *const c_char,
+                                  length: c_ulong)
+                                  -> c_int;
+    pub fn mysql_real_query_cont(ret: *mut c_int, mysql: *mut MYSQL, status: c_int) -> c_int;
+    pub fn mysql_send_query_start(ret: *mut c_int,
+                                  mysql: *mut MYSQL,
+                                  query: *const c_char,
+                                  length: c_ulong)
+                                  -> c_int;
+    pub fn mysql_send_query_cont(ret: *mut c_int, mysql: *mut MYSQL, status: c_int) -> c_int;
+    pub fn mysql_read_query_result_start(ret: *mut my_bool, mysql: *mut MYSQL) -> c_int;
+    pub fn mysql_read_query_result_cont(ret: *mut my_bool, mysql: *mut MYSQL, status: c_int)
+                                        -> c_int;
+    pub fn mysql_store_result_start(ret: *mut *mut MYSQL_RES, mysql: *mut MYSQL) -> c_int;
+    pub fn mysql_store_result_cont(ret: *mut *mut MYSQL_RES, mysql: *mut MYSQL, status: c_int)
+                                   -> c_int;
+    pub fn mysql_next_result_start(ret: *mut c_int, mysql: *mut MYSQL) -> c_int;
+    pub fn mysql_next_result_cont(ret: *mut c_int, mysql: *mut MYSQL, status: c_int) -> c_int;
+    pub fn mysql_free_result_start(result: *mut MYSQL_RES) -> c_int;
+    pub fn mysql_free_result_cont(result: *mut MYSQL_RES, status: c_int) -> c_int;
+    pub fn mysql_select_db_start(ret: *mut c_int, mysql: *mut MYSQL, db: *const c_char) -> c_int;
+    pub fn mysql_select_db_cont(ret: *mut c_int, mysql: *mut MYSQL, status: c_int) -> c_int;
+    pub fn mysql_ping_start(ret: *mut c_int, mysql: *mut MYSQL) -> c_int;
+    pub fn mysql_ping_cont(ret: *mut c_int, mysql: *mut MYSQL, status: c_int) -> c_int;
+    pub fn mysql_reset_connection_start(ret: *mut c_int, mysql: *mut MYSQL) -> c_int;
+    pub fn mysql_reset_connection_cont(ret: *mut c_int, mysql: *mut MYSQL, status: c_int)
+                                       -> c_int;
+    pub fn mysql_close_start(mysql: *mut MYSQL) -> c_int;
+    pub fn mysql_close_cont(mysql: *mut MYSQL, status: c_int) -> c_int;
+}
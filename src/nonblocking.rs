@@ -0,0 +1,47 @@
+//! Bindings for the nonblocking API added in MySQL 8.0.16.
+//!
+//! These functions are not part of the generated bindings, which are built
+//! from older headers, and only link against libmysqlclient 8.0.16 or newer.
+
+use std::os::raw::{c_char, c_uint, c_ulong};
+
+use {MYSQL, MYSQL_RES};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum net_async_status {
+    NET_ASYNC_COMPLETE = 0,
+    NET_ASYNC_NOT_READY = 1,
+    NET_ASYNC_ERROR = 2,
+    NET_ASYNC_COMPLETE_NO_MORE_RESULTS = 3,
+}
+
+extern "C" {
+    pub fn mysql_real_connect_nonblocking(mysql: *mut MYSQL,
+                                          host: *const c_char,
+                                          user: *const c_char,
+                                          passwd: *const c_char,
+                                          db: *const c_char,
+                                          port: c_uint,
+                                          unix_socket: *const c_char,
+                                          clientflag: c_ulong)
+                                          -> net_async_status;
+    pub fn mysql_send_query_nonblocking(mysql: *mut MYSQL,
+                                        query: *const c_char,
+                                        length: c_ulong)
+                                        -> net_async_status;
+    pub fn mysql_real_query_nonblocking(mysql: *mut MYSQL,
+                                        query: *const c_char,
+                                        length: c_ulong)
+                                        -> net_async_status;
+    pub fn mysql_store_result_nonblocking(mysql: *mut MYSQL,
+                                          result: *mut *mut MYSQL_RES)
+                                          -> net_async_status;
+    pub fn mysql_next_result_nonblocking(mysql: *mut MYSQL) -> net_async_status;
+    pub fn mysql_select_db_nonblocking(mysql: *mut MYSQL,
+                                       db: *const c_char,
+                                       error: *mut bool)
+                                       -> net_async_status;
+    pub fn mysql_free_result_nonblocking(result: *mut MYSQL_RES) -> net_async_status;
+    pub fn mysql_reset_connection_nonblocking(mysql: *mut MYSQL) -> net_async_status;
+}
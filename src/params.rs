@@ -0,0 +1,260 @@
+//! Construction of `MYSQL_BIND` arrays for prepared statement parameters.
+
+use std::mem;
+use std::os::raw::{c_ulong, c_void};
+use std::ptr;
+
+use binder::Value;
+use datetime::TimeBuffer;
+use {MYSQL_BIND, MYSQL_TIME, enum_field_types};
+
+/// Backing storage for a parameter. Every variant keeps its data on the heap
+/// so that the pointers handed to `mysql_stmt_bind_param` stay valid when the
+/// `Params` is moved.
+#[derive(Debug, Clone)]
+enum Storage {
+    Null,
+    /// Integers and floats, stored in the first bytes of a `u64`.
+    Scalar(Box<u64>),
+    Bytes(Vec<u8>),
+    Time(Box<TimeBuffer>),
+}
+
+/// A single prepared statement parameter.
+#[derive(Debug, Clone)]
+pub struct Param {
+    buffer_type: enum_field_types,
+    is_unsigned: bool,
+    storage: Storage,
+}
+
+impl Param {
+    pub fn null() -> Self {
+        Param {
+            buffer_type: enum_field_types::MYSQL_TYPE_NULL,
+            is_unsigned: false,
+            storage: Storage::Null,
+        }
+    }
+
+    fn scalar<T: Copy>(buffer_type: enum_field_types, is_unsigned: bool, value: T) -> Self {
+        assert!(mem::size_of::<T>() <= mem::size_of::<u64>());
+        let mut storage = Box::new(0u64);
+        unsafe { ptr::write(&mut *storage as *mut u64 as *mut T, value) };
+        Param {
+            buffer_type,
+            is_unsigned,
+            storage: Storage::Scalar(storage),
+        }
+    }
+
+    /// A parameter sent as `buffer_type` with the given bytes, such as
+    /// `MYSQL_TYPE_STRING` or `MYSQL_TYPE_BLOB`.
+    pub fn bytes(buffer_type: enum_field_types, bytes: Vec<u8>) -> Self {
+        Param {
+            buffer_type,
+            is_unsigned: false,
+            storage: Storage::Bytes(bytes),
+        }
+    }
+
+    /// A placeholder for a parameter whose value is streamed with
+    /// `Statement::send_long_data` after binding, such as a large
+    /// `MYSQL_TYPE_BLOB` or `MYSQL_TYPE_STRING`.
+    pub fn long_data(buffer_type: enum_field_types) -> Self {
+        Param::bytes(buffer_type, Vec::new())
+    }
+
+    pub fn buffer_type(&self) -> enum_field_types {
+        self.buffer_type
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self.storage, Storage::Null)
+    }
+
+    /// The value of the parameter, read back according to its buffer type.
+    pub fn value(&self) -> Value<'_> {
+        use enum_field_types::*;
+
+        let scalar = match self.storage {
+            Storage::Null => return Value::Null,
+            Storage::Bytes(ref bytes) => return Value::Bytes(bytes),
+            Storage::Time(ref time) => return Value::Time(time.time),
+            Storage::Scalar(ref value) => &**value as *const u64,
+        };
+        unsafe {
+            match (self.buffer_type, self.is_unsigned) {
+                (MYSQL_TYPE_TINY, false) => Value::Int(*(scalar as *const i8) as i64),
+                (MYSQL_TYPE_TINY, true) => Value::UInt(*(scalar as *const u8) as u64),
+                (MYSQL_TYPE_SHORT, false) => Value::Int(*(scalar as *const i16) as i64),
+                (MYSQL_TYPE_SHORT, true) => Value::UInt(*(scalar as *const u16) as u64),
+                (MYSQL_TYPE_LONG, false) => Value::Int(*(scalar as *const i32) as i64),
+                (MYSQL_TYPE_LONG, true) => Value::UInt(*(scalar as *const u32) as u64),
+                (MYSQL_TYPE_FLOAT, _) => Value::Float(*(scalar as *const f32)),
+                (MYSQL_TYPE_DOUBLE, _) => Value::Double(*(scalar as *const f64)),
+                (_, false) => Value::Int(*(scalar as *const i64)),
+                (_, true) => Value::UInt(*scalar),
+            }
+        }
+    }
+
+    fn as_bind(&mut self) -> MYSQL_BIND {
+        let mut bind: MYSQL_BIND = unsafe { mem::zeroed() };
+        bind.buffer_type = self.buffer_type;
+        bind.is_unsigned = self.is_unsigned as _;
+        let (buffer, length) = match self.storage {
+            Storage::Null => (ptr::null_mut(), 0),
+            Storage::Scalar(ref mut value) => {
+                (&mut **value as *mut u64 as *mut c_void, mem::size_of::<u64>())
+            }
+            Storage::Bytes(ref mut bytes) => (bytes.as_mut_ptr() as *mut c_void, bytes.len()),
+            Storage::Time(ref mut time) => {
+                (&mut **time as *mut TimeBuffer as *mut c_void, mem::size_of::<TimeBuffer>())
+            }
+        };
+        bind.buffer = buffer;
+        bind.buffer_length = length as c_ulong;
+        bind
+    }
+}
+
+/// Types that can be bound as a prepared statement parameter.
+pub trait ToSqlParam {
+    fn to_sql_param(&self) -> Param;
+}
+
+macro_rules! scalar_to_sql_param {
+    ($($ty:ty => $buffer_type:ident, $unsigned:expr;)*) => {$(
+        impl ToSqlParam for $ty {
+            fn to_sql_param(&self) -> Param {
+                Param::scalar(enum_field_types::$buffer_type, $unsigned, *self)
+            }
+        }
+    )*}
+}
+
+scalar_to_sql_param! {
+    i8 => MYSQL_TYPE_TINY, false;
+    u8 => MYSQL_TYPE_TINY, true;
+    i16 => MYSQL_TYPE_SHORT, false;
+    u16 => MYSQL_TYPE_SHORT, true;
+    i32 => MYSQL_TYPE_LONG, false;
+    u32 => MYSQL_TYPE_LONG, true;
+    i64 => MYSQL_TYPE_LONGLONG, false;
+    u64 => MYSQL_TYPE_LONGLONG, true;
+    f32 => MYSQL_TYPE_FLOAT, false;
+    f64 => MYSQL_TYPE_DOUBLE, false;
+}
+
+impl ToSqlParam for bool {
+    fn to_sql_param(&self) -> Param {
+        (*self as i8).to_sql_param()
+    }
+}
+
+impl ToSqlParam for str {
+    fn to_sql_param(&self) -> Param {
+        Param::bytes(enum_field_types::MYSQL_TYPE_STRING, self.as_bytes().to_vec())
+    }
+}
+
+impl ToSqlParam for String {
+    fn to_sql_param(&self) -> Param {
+        self.as_str().to_sql_param()
+    }
+}
+
+impl ToSqlParam for [u8] {
+    fn to_sql_param(&self) -> Param {
+        Param::bytes(enum_field_types::MYSQL_TYPE_BLOB, self.to_vec())
+    }
+}
+
+impl ToSqlParam for Vec<u8> {
+    fn to_sql_param(&self) -> Param {
+        self.as_slice().to_sql_param()
+    }
+}
+
+/// The buffer type is derived from `time_type`, so a `TIME` value is not
+/// sent as a `DATETIME`.
+impl ToSqlParam for MYSQL_TIME {
+    fn to_sql_param(&self) -> Param {
+        use enum_mysql_timestamp_type::*;
+
+        let buffer_type = match self.time_type {
+            MYSQL_TIMESTAMP_DATE => enum_field_types::MYSQL_TYPE_DATE,
+            MYSQL_TIMESTAMP_TIME => enum_field_types::MYSQL_TYPE_TIME,
+            _ => enum_field_types::MYSQL_TYPE_DATETIME,
+        };
+        Param {
+            buffer_type,
+            is_unsigned: false,
+            storage: Storage::Time(Box::new(TimeBuffer::new(*self))),
+        }
+    }
+}
+
+impl<T: ToSqlParam> ToSqlParam for Option<T> {
+    fn to_sql_param(&self) -> Param {
+        match *self {
+            Some(ref value) => value.to_sql_param(),
+            None => Param::null(),
+        }
+    }
+}
+
+impl<T: ToSqlParam + ?Sized> ToSqlParam for &T {
+    fn to_sql_param(&self) -> Param {
+        (**self).to_sql_param()
+    }
+}
+
+impl ToSqlParam for Param {
+    fn to_sql_param(&self) -> Param {
+        self.clone()
+    }
+}
+
+/// An ordered list of parameters for a prepared statement, owning the
+/// buffers that its `MYSQL_BIND` array points to.
+#[derive(Debug, Clone, Default)]
+pub struct Params {
+    params: Vec<Param>,
+    binds: Vec<MYSQL_BIND>,
+}
+
+// The only pointers held are into the heap buffers owned by `params`.
+unsafe impl Send for Params {}
+
+impl Params {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<T: ToSqlParam>(mut self, value: T) -> Self {
+        self.params.push(value.to_sql_param());
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&Param> {
+        self.params.get(idx)
+    }
+
+    /// Builds the `MYSQL_BIND` array pointing into the parameter buffers.
+    ///
+    /// The returned pointer is valid until `self` is modified or dropped.
+    pub(crate) fn binds_mut(&mut self) -> *mut MYSQL_BIND {
+        self.binds = self.params.iter_mut().map(Param::as_bind).collect();
+        self.binds.as_mut_ptr()
+    }
+}
@@ -0,0 +1,528 @@
+//! A blocking pool of connections shared between threads.
+
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use connection::{ConnectOptions, Connection};
+use error::{Error, Result};
+
+/// Sizing and expiry settings of a `Pool`.
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    min_idle: usize,
+    max_size: usize,
+    idle_timeout: Option<Duration>,
+    max_lifetime: Option<Duration>,
+    checkout_timeout: Option<Duration>,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        PoolOptions {
+            min_idle: 0,
+            max_size: 10,
+            idle_timeout: Some(Duration::from_secs(10 * 60)),
+            max_lifetime: Some(Duration::from_secs(30 * 60)),
+            checkout_timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl PoolOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of connections opened up front and kept open even when
+    /// they exceed the idle timeout. Connections closed by the pool are
+    /// replaced until this many are open again. Defaults to 0.
+    pub fn min_idle(mut self, min_idle: usize) -> Self {
+        self.min_idle = min_idle;
+        self
+    }
+
+    /// The maximum number of open connections. Defaults to 10.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// How long a connection may stay unused before it is closed. Defaults
+    /// to 10 minutes.
+    pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// How long a connection is reused before it is closed. Defaults to 30
+    /// minutes, which should stay below the server's `wait_timeout`.
+    pub fn max_lifetime(mut self, lifetime: Option<Duration>) -> Self {
+        self.max_lifetime = lifetime;
+        self
+    }
+
+    /// How long `Pool::get` waits for a connection when all of them are in
+    /// use. `None` waits indefinitely. Defaults to 30 seconds.
+    pub fn checkout_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.checkout_timeout = timeout;
+        self
+    }
+}
+
+struct IdleConnection {
+    conn: Connection,
+    created: Instant,
+    idle_since: Instant,
+}
+
+struct State {
+    idle: VecDeque<IdleConnection>,
+    /// Idle and checked out connections, including those being opened.
+    total: usize,
+}
+
+/// Opens the pool's connections, which is `Connection::connect` outside of
+/// tests.
+type Connect = Box<dyn Fn(&ConnectOptions) -> Result<Connection> + Send + Sync>;
+
+struct Shared {
+    opts: ConnectOptions,
+    connect: Connect,
+    config: PoolOptions,
+    state: Mutex<State>,
+    released: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn expired(&self, created: Instant) -> bool {
+        self.config.max_lifetime.is_some_and(|lifetime| created.elapsed() >= lifetime)
+    }
+
+    /// Gives up the slot of a connection that was closed.
+    fn discard(&self) {
+        self.lock().total -= 1;
+        self.released.notify_one();
+    }
+
+    fn check_in(&self, mut conn: Connection, created: Instant) {
+        if self.expired(created) || conn.in_transaction() || conn.has_pending_results() ||
+           self.reset(&mut conn).is_err() {
+            drop(conn);
+            self.discard();
+        } else {
+            self.lock().idle.push_back(IdleConnection {
+                conn,
+                created,
+                idle_since: Instant::now(),
+            });
+            self.released.notify_one();
+        }
+        self.maintain();
+    }
+
+    /// Closes idle connections past their lifetime or idle timeout, then
+    /// opens new ones until `min_idle` connections are open.
+    fn maintain(&self) {
+        let stale = self.reap(&mut self.lock());
+        if !stale.is_empty() {
+            // Closed outside of the lock, since `mysql_close` talks to the
+            // server.
+            drop(stale);
+            self.released.notify_all();
+        }
+        self.replenish();
+    }
+
+    /// Removes the idle connections that should be closed. Those only past
+    /// the idle timeout are kept while no more than `min_idle` are open.
+    fn reap(&self, state: &mut State) -> Vec<IdleConnection> {
+        let mut stale = Vec::new();
+        let mut i = 0;
+        while i < state.idle.len() {
+            let idle = &state.idle[i];
+            let idle_expired = self.config
+                .idle_timeout
+                .is_some_and(|timeout| idle.idle_since.elapsed() >= timeout);
+            let keep_for_min = state.total <= self.config.min_idle;
+            if self.expired(idle.created) || (idle_expired && !keep_for_min) {
+                stale.extend(state.idle.remove(i));
+                state.total -= 1;
+            } else {
+                i += 1;
+            }
+        }
+        stale
+    }
+
+    /// Opens connections until `min_idle` are open, stopping at the first
+    /// one that fails.
+    fn replenish(&self) {
+        loop {
+            {
+                let mut state = self.lock();
+                if state.total >= self.config.min_idle {
+                    return;
+                }
+                state.total += 1;
+            }
+            match (self.connect)(&self.opts) {
+                Ok(conn) => {
+                    let now = Instant::now();
+                    self.lock().idle.push_back(IdleConnection {
+                        conn,
+                        created: now,
+                        idle_since: now,
+                    });
+                    self.released.notify_one();
+                }
+                Err(_) => return self.discard(),
+            }
+        }
+    }
+
+    /// Resets the session, falling back to `mysql_change_user` for servers
+    /// without `COM_RESET_CONNECTION`.
+    fn reset(&self, conn: &mut Connection) -> Result<()> {
+        conn.reset().or_else(|_| conn.change_user(&self.opts))
+    }
+}
+
+/// A pool of connections opened with the same `ConnectOptions`.
+///
+/// Connections are checked with `mysql_ping` when checked out, and reset
+/// when returned. Connections returned with an open transaction or unread
+/// results are closed instead. Idle connections past their maximum lifetime
+/// or idle timeout are closed whenever a connection is checked out or
+/// returned.
+#[derive(Clone)]
+pub struct Pool {
+    shared: Arc<Shared>,
+}
+
+impl Pool {
+    /// Creates a pool, opening `min_idle` connections immediately.
+    ///
+    /// Fails with `Error::InvalidPoolOptions` if `max_size` is 0 or smaller
+    /// than `min_idle`.
+    pub fn new(opts: ConnectOptions, config: PoolOptions) -> Result<Self> {
+        Pool::with_connect(opts, config, Box::new(Connection::connect))
+    }
+
+    fn with_connect(opts: ConnectOptions, config: PoolOptions, connect: Connect) -> Result<Self> {
+        if config.max_size == 0 {
+            return Err(Error::InvalidPoolOptions("max_size must be at least 1"));
+        }
+        if config.min_idle > config.max_size {
+            return Err(Error::InvalidPoolOptions("min_idle exceeds max_size"));
+        }
+        let mut idle = VecDeque::with_capacity(config.min_idle);
+        for _ in 0..config.min_idle {
+            let now = Instant::now();
+            idle.push_back(IdleConnection {
+                conn: connect(&opts)?,
+                created: now,
+                idle_since: now,
+            });
+        }
+        let total = idle.len();
+        Ok(Pool {
+            shared: Arc::new(Shared {
+                opts,
+                connect,
+                config,
+                state: Mutex::new(State { idle, total }),
+                released: Condvar::new(),
+            }),
+        })
+    }
+
+    /// Checks out a connection, opening a new one if none is idle and the
+    /// pool is not full.
+    ///
+    /// Fails with `Error::PoolTimeout` if no connection became available
+    /// within the checkout timeout.
+    pub fn get(&self) -> Result<PooledConnection> {
+        let shared = &*self.shared;
+        let deadline = shared.config.checkout_timeout.map(|timeout| Instant::now() + timeout);
+        shared.maintain();
+        let mut state = shared.lock();
+        loop {
+            if let Some(mut idle) = state.idle.pop_back() {
+                drop(state);
+                if shared.expired(idle.created) || idle.conn.ping().is_err() {
+                    drop(idle);
+                    shared.discard();
+                } else {
+                    return Ok(PooledConnection {
+                        conn: Some(idle.conn),
+                        created: idle.created,
+                        shared: self.shared.clone(),
+                    });
+                }
+            } else if state.total < shared.config.max_size {
+                state.total += 1;
+                drop(state);
+                return match (shared.connect)(&shared.opts) {
+                    Ok(conn) => {
+                        Ok(PooledConnection {
+                            conn: Some(conn),
+                            created: Instant::now(),
+                            shared: self.shared.clone(),
+                        })
+                    }
+                    Err(e) => {
+                        shared.discard();
+                        Err(e)
+                    }
+                };
+            } else {
+                state = match deadline {
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            return Err(Error::PoolTimeout);
+                        }
+                        shared.released
+                            .wait_timeout(state, deadline - now)
+                            .unwrap_or_else(|e| e.into_inner())
+                            .0
+                    }
+                    None => shared.released.wait(state).unwrap_or_else(|e| e.into_inner()),
+                };
+                continue;
+            }
+            state = shared.lock();
+        }
+    }
+
+    /// The number of open connections, idle or checked out.
+    pub fn size(&self) -> usize {
+        self.shared.lock().total
+    }
+
+    pub fn idle_count(&self) -> usize {
+        self.shared.lock().idle.len()
+    }
+}
+
+/// A connection checked out of a `Pool`, returned to it on drop.
+///
+/// Returning a connection resets its session with `mysql_reset_connection`,
+/// which talks to the server on the dropping thread.
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    created: Instant,
+    shared: Arc<Shared>,
+}
+
+impl PooledConnection {
+    /// Removes the connection from the pool, freeing its slot.
+    pub fn detach(mut self) -> Connection {
+        let conn = self.conn.take().expect("connection already returned");
+        self.shared.discard();
+        conn
+    }
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection already returned")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection already returned")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.shared.check_in(conn, self.created);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+    use std::thread;
+
+    use super::*;
+
+    /// Longer than the idle timeout and lifetime used below.
+    const STALE: Duration = Duration::from_secs(1);
+
+    fn refused() -> Error {
+        Error::Io(io::Error::new(io::ErrorKind::ConnectionRefused, "refused"))
+    }
+
+    fn assert_refused<T>(result: Result<T>) {
+        match result {
+            Err(Error::Io(ref e)) if e.kind() == io::ErrorKind::ConnectionRefused => {}
+            Err(e) => panic!("unexpected error {:?}", e),
+            Ok(_) => panic!("unexpected success"),
+        }
+    }
+
+    /// Opens unconnected handles, which is enough for connections that stay
+    /// idle, and fails once `limit` have been opened.
+    fn connect_up_to(limit: usize, opened: Arc<AtomicUsize>) -> Connect {
+        Box::new(move |_| {
+            if opened.fetch_add(1, Ordering::SeqCst) < limit {
+                Connection::init()
+            } else {
+                Err(refused())
+            }
+        })
+    }
+
+    fn pool(config: PoolOptions, connect: Connect) -> Result<Pool> {
+        Pool::with_connect(ConnectOptions::new(), config, connect)
+    }
+
+    fn config(min_idle: usize, max_size: usize) -> PoolOptions {
+        PoolOptions::new().min_idle(min_idle).max_size(max_size)
+    }
+
+    fn add_idle(pool: &Pool, idle_since: Instant) {
+        let mut state = pool.shared.lock();
+        state.idle.push_back(IdleConnection {
+            conn: Connection::init().unwrap(),
+            created: Instant::now(),
+            idle_since,
+        });
+        state.total += 1;
+    }
+
+    fn assert_invalid(config: PoolOptions) {
+        match pool(config, connect_up_to(0, Arc::default())) {
+            Err(Error::InvalidPoolOptions(_)) => {}
+            Err(e) => panic!("unexpected error {:?}", e),
+            Ok(_) => panic!("unexpected success"),
+        }
+    }
+
+    #[test]
+    fn options_are_validated() {
+        assert_invalid(config(0, 0));
+        assert_invalid(config(3, 2));
+        assert!(pool(config(0, 1), connect_up_to(0, Arc::default())).is_ok());
+    }
+
+    #[test]
+    fn min_idle_connections_are_opened_up_front() {
+        let opened = Arc::new(AtomicUsize::new(0));
+        let pool = pool(config(2, 3), connect_up_to(2, opened.clone())).unwrap();
+        assert_eq!((pool.size(), pool.idle_count()), (2, 2));
+        assert_eq!(opened.load(Ordering::SeqCst), 2);
+        assert_refused(self::pool(config(2, 3), connect_up_to(1, Arc::default())));
+    }
+
+    #[test]
+    fn failed_connects_free_their_slot() {
+        let pool = pool(config(0, 1), connect_up_to(0, Arc::default())).unwrap();
+        assert_refused(pool.get());
+        assert_refused(pool.get());
+        assert_eq!(pool.size(), 0);
+    }
+
+    #[test]
+    fn idle_connections_expire_down_to_min_idle() {
+        let opened = Arc::new(AtomicUsize::new(0));
+        let config = config(1, 3).idle_timeout(Some(STALE / 2)).max_lifetime(None);
+        let pool = pool(config, connect_up_to(1, opened.clone())).unwrap();
+        let stale = Instant::now() - STALE;
+        add_idle(&pool, stale);
+        add_idle(&pool, stale);
+        assert_eq!(pool.size(), 3);
+        pool.shared.maintain();
+        assert_eq!((pool.size(), pool.idle_count()), (1, 1));
+        // The last connection is kept for `min_idle` once it is stale too.
+        pool.shared.lock().idle[0].idle_since = stale;
+        pool.shared.maintain();
+        assert_eq!((pool.size(), pool.idle_count()), (1, 1));
+        assert_eq!(opened.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn expired_connections_are_replaced_up_to_min_idle() {
+        let opened = Arc::new(AtomicUsize::new(0));
+        let config = config(2, 3).idle_timeout(None).max_lifetime(Some(STALE / 2));
+        let pool = pool(config, connect_up_to(3, opened.clone())).unwrap();
+        for idle in pool.shared.lock().idle.iter_mut() {
+            idle.created -= STALE;
+        }
+        // One replacement opens, then replenishing stops at the failure.
+        pool.shared.maintain();
+        assert_eq!((pool.size(), pool.idle_count()), (1, 1));
+        assert_eq!(opened.load(Ordering::SeqCst), 4);
+    }
+
+    /// A connect function that signals `entered` and then fails once it is
+    /// sent a `release`.
+    fn blocking_connect() -> (Connect, mpsc::Receiver<()>, mpsc::Sender<()>) {
+        let (entered_tx, entered) = mpsc::channel();
+        let (release, release_rx) = mpsc::channel();
+        let (entered_tx, release_rx) = (Mutex::new(entered_tx), Mutex::new(release_rx));
+        let connect: Connect = Box::new(move |_| {
+            entered_tx.lock().unwrap().send(()).unwrap();
+            release_rx.lock().unwrap().recv().unwrap();
+            Err(refused())
+        });
+        (connect, entered, release)
+    }
+
+    fn spawn_get(pool: &Pool) -> thread::JoinHandle<Result<()>> {
+        let pool = pool.clone();
+        thread::spawn(move || pool.get().map(drop))
+    }
+
+    #[test]
+    fn checkouts_time_out_while_the_pool_is_full() {
+        let (connect, entered, release) = blocking_connect();
+        let timeout = Duration::from_millis(50);
+        let pool = pool(config(0, 1).checkout_timeout(Some(timeout)), connect).unwrap();
+        let first = spawn_get(&pool);
+        entered.recv().unwrap();
+        let start = Instant::now();
+        match pool.get().map(drop) {
+            Err(Error::PoolTimeout) => assert!(start.elapsed() >= timeout),
+            other => panic!("unexpected result {:?}", other),
+        }
+        release.send(()).unwrap();
+        assert_refused(first.join().unwrap());
+        assert_eq!(pool.size(), 0);
+    }
+
+    #[test]
+    fn waiting_checkouts_take_a_freed_slot() {
+        let (connect, entered, release) = blocking_connect();
+        let timeout = Duration::from_secs(60);
+        let pool = pool(config(0, 1).checkout_timeout(Some(timeout)), connect).unwrap();
+        let first = spawn_get(&pool);
+        entered.recv().unwrap();
+        let start = Instant::now();
+        let second = spawn_get(&pool);
+        thread::sleep(Duration::from_millis(50));
+        release.send(()).unwrap();
+        assert_refused(first.join().unwrap());
+        // The second checkout was woken by the failure and connects itself.
+        entered.recv().unwrap();
+        release.send(()).unwrap();
+        assert_refused(second.join().unwrap());
+        assert!(start.elapsed() < timeout);
+        assert_eq!(pool.size(), 0);
+    }
+}
@@ -0,0 +1,224 @@
+use std::os::raw::{c_char, c_ulong};
+use std::ptr::NonNull;
+use std::result;
+use std::slice;
+
+use column::Column;
+use connection::Connection;
+use decode::{DecodeError, FromSqlText};
+use error::Result;
+use {MYSQL_RES, MYSQL_ROW_OFFSET, mysql_data_seek, mysql_fetch_fields, mysql_fetch_lengths,
+     mysql_fetch_row, mysql_free_result, mysql_num_fields, mysql_num_rows, mysql_row_seek,
+     mysql_row_tell};
+
+/// A single row of a text protocol result set.
+///
+/// The row borrows from the result it was fetched from and is invalidated by
+/// the next fetch.
+#[derive(Clone, Copy)]
+pub struct Row<'a> {
+    values: &'a [*mut c_char],
+    lengths: &'a [c_ulong],
+    columns: &'a [Column],
+}
+
+impl<'a> Row<'a> {
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the raw bytes of column `idx`, or `None` if it is `NULL`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<&'a [u8]> {
+        let value = self.values[idx];
+        if value.is_null() {
+            None
+        } else {
+            unsafe { Some(slice::from_raw_parts(value as *const u8, self.lengths[idx] as usize)) }
+        }
+    }
+
+    pub fn is_null(&self, idx: usize) -> bool {
+        self.values[idx].is_null()
+    }
+
+    pub fn columns(&self) -> &'a [Column] {
+        self.columns
+    }
+
+    /// Decodes column `idx` into `T` according to the column's metadata.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn decode<T: FromSqlText>(&self, idx: usize) -> result::Result<T, DecodeError> {
+        T::from_sql_text(&self.columns[idx], self.get(idx))
+    }
+}
+
+/// The parts shared by buffered and streaming results.
+pub(crate) struct RawResult {
+    ptr: NonNull<MYSQL_RES>,
+    num_fields: usize,
+}
+
+impl RawResult {
+    pub(crate) unsafe fn new(ptr: *mut MYSQL_RES) -> Self {
+        let ptr = NonNull::new(ptr).expect("MYSQL_RES must not be null");
+        let num_fields = mysql_num_fields(ptr.as_ptr()) as usize;
+        RawResult { ptr, num_fields }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *mut MYSQL_RES {
+        self.ptr.as_ptr()
+    }
+
+    pub(crate) fn columns(&self) -> &[Column] {
+        unsafe { Column::slice(mysql_fetch_fields(self.as_ptr()), self.num_fields) }
+    }
+
+    fn fetch(&mut self) -> Option<Row<'_>> {
+        unsafe {
+            let row = mysql_fetch_row(self.as_ptr());
+            if row.is_null() {
+                return None;
+            }
+            let lengths = mysql_fetch_lengths(self.as_ptr());
+            Some(Row {
+                values: slice::from_raw_parts(row, self.num_fields),
+                lengths: slice::from_raw_parts(lengths, self.num_fields),
+                columns: self.columns(),
+            })
+        }
+    }
+}
+
+impl Drop for RawResult {
+    fn drop(&mut self) {
+        unsafe { mysql_free_result(self.as_ptr()) }
+    }
+}
+
+/// A result set read into client memory by `mysql_store_result`.
+///
+/// All rows have already been transferred, so the result is independent of
+/// the connection and supports random access.
+pub struct BufferedResult {
+    raw: RawResult,
+}
+
+unsafe impl Send for BufferedResult {}
+
+/// A position in a `BufferedResult`, as returned by `mysql_row_tell`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowOffset {
+    result: *mut MYSQL_RES,
+    offset: MYSQL_ROW_OFFSET,
+}
+
+impl BufferedResult {
+    pub(crate) unsafe fn from_raw(ptr: *mut MYSQL_RES) -> Self {
+        BufferedResult { raw: RawResult::new(ptr) }
+    }
+
+    pub fn as_ptr(&self) -> *mut MYSQL_RES {
+        self.raw.as_ptr()
+    }
+
+    pub fn num_fields(&self) -> usize {
+        self.raw.num_fields
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        self.raw.columns()
+    }
+
+    pub fn num_rows(&self) -> u64 {
+        unsafe { mysql_num_rows(self.as_ptr()) as u64 }
+    }
+
+    pub fn next_row(&mut self) -> Option<Row<'_>> {
+        self.raw.fetch()
+    }
+
+    /// Moves the cursor to the zero-based row number `row`.
+    pub fn data_seek(&mut self, row: u64) {
+        unsafe { mysql_data_seek(self.as_ptr(), row as _) }
+    }
+
+    pub fn row_tell(&self) -> RowOffset {
+        RowOffset {
+            result: self.as_ptr(),
+            offset: unsafe { mysql_row_tell(self.as_ptr()) },
+        }
+    }
+
+    /// Moves the cursor to a position previously returned by `row_tell`,
+    /// returning the previous position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` was obtained from a different result.
+    pub fn row_seek(&mut self, offset: RowOffset) -> RowOffset {
+        assert_eq!(offset.result, self.as_ptr(), "RowOffset belongs to a different result");
+        RowOffset {
+            result: self.as_ptr(),
+            offset: unsafe { mysql_row_seek(self.as_ptr(), offset.offset) },
+        }
+    }
+}
+
+/// A result set read row by row by `mysql_use_result`.
+///
+/// Until every row has been read the connection cannot execute anything
+/// else, so the result holds a mutable borrow of it. Dropping the result
+/// drains the remaining rows.
+pub struct StreamingResult<'conn> {
+    raw: RawResult,
+    conn: &'conn mut Connection,
+}
+
+impl<'conn> StreamingResult<'conn> {
+    pub(crate) unsafe fn from_raw(ptr: *mut MYSQL_RES, conn: &'conn mut Connection) -> Self {
+        StreamingResult { raw: RawResult::new(ptr), conn }
+    }
+
+    pub fn as_ptr(&self) -> *mut MYSQL_RES {
+        self.raw.as_ptr()
+    }
+
+    pub fn num_fields(&self) -> usize {
+        self.raw.num_fields
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        self.raw.columns()
+    }
+
+    /// Reads the next row from the server.
+    ///
+    /// Unlike a buffered result, reaching the end of the rows and a network
+    /// error are both reported by `mysql_fetch_row` as `NULL`, so the
+    /// connection's error state is checked to tell them apart.
+    pub fn next_row(&mut self) -> Result<Option<Row<'_>>> {
+        let conn = &*self.conn;
+        match self.raw.fetch() {
+            Some(row) => Ok(Some(row)),
+            None if conn.errno() == 0 => Ok(None),
+            None => Err(conn.last_error()),
+        }
+    }
+}
+
+impl<'conn> Drop for StreamingResult<'conn> {
+    fn drop(&mut self) {
+        unsafe { while !mysql_fetch_row(self.as_ptr()).is_null() {} }
+    }
+}
@@ -0,0 +1,188 @@
+//! Iteration over the results of multi-statement queries and stored
+//! procedure calls.
+
+use binder::{BoundResult, ResultBinder};
+use connection::Connection;
+use error::Result;
+use result::BufferedResult;
+use statement::Statement;
+use {SERVER_PS_OUT_PARAMS, mysql_next_result, mysql_stmt_next_result};
+
+/// The outcome of a statement that did not produce a result set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Status {
+    pub affected_rows: u64,
+    pub insert_id: u64,
+    pub warnings: u32,
+}
+
+impl Connection {
+    fn status(&self) -> Status {
+        Status {
+            affected_rows: self.affected_rows(),
+            insert_id: self.insert_id(),
+            warnings: self.warning_count(),
+        }
+    }
+
+    /// Sends `sql` and returns an iterator over each of its results.
+    ///
+    /// Several statements separated by `;` can only be sent if the connection
+    /// was opened with `CLIENT_MULTI_STATEMENTS`. `CALL` also returns several
+    /// results, the last one being the status of the call itself.
+    pub fn query_results(&mut self, sql: &str) -> Result<Results<'_>> {
+        self.query(sql)?;
+        Ok(Results {
+            conn: self,
+            started: false,
+            done: false,
+        })
+    }
+}
+
+/// One result of a multi-statement query.
+pub enum QueryResult {
+    /// A statement returned rows.
+    Rows(BufferedResult),
+    /// A statement did not return rows.
+    Status(Status),
+}
+
+/// An iterator over the results of a query, advanced with
+/// `mysql_next_result`.
+///
+/// The server stops executing the remaining statements after one fails, so
+/// an error is always the last item. Dropping the iterator reads and discards
+/// any remaining results, leaving the connection ready for the next query.
+pub struct Results<'conn> {
+    conn: &'conn mut Connection,
+    started: bool,
+    done: bool,
+}
+
+impl<'conn> Iterator for Results<'conn> {
+    type Item = Result<QueryResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.started {
+            match unsafe { mysql_next_result(self.conn.as_ptr()) } {
+                0 => {}
+                -1 => {
+                    self.done = true;
+                    return None;
+                }
+                _ => {
+                    self.done = true;
+                    return Some(Err(self.conn.last_error()));
+                }
+            }
+        }
+        self.started = true;
+        let item = match self.conn.store_result() {
+            Ok(Some(rows)) => Ok(QueryResult::Rows(rows)),
+            Ok(None) => Ok(QueryResult::Status(self.conn.status())),
+            Err(e) => {
+                self.done = true;
+                Err(e)
+            }
+        };
+        Some(item)
+    }
+}
+
+impl<'conn> Drop for Results<'conn> {
+    fn drop(&mut self) {
+        for _ in self {}
+    }
+}
+
+/// One result of an executed prepared statement.
+pub enum StatementResult<'stmt, 'conn: 'stmt> {
+    /// The statement returned rows.
+    Rows(BoundResult<'stmt, 'conn>),
+    /// The values of the `OUT` and `INOUT` parameters of a procedure called
+    /// with `CALL`, marked by `SERVER_PS_OUT_PARAMS`.
+    OutParams(BoundResult<'stmt, 'conn>),
+    /// The statement did not return rows.
+    Status(Status),
+}
+
+impl<'conn> Statement<'conn> {
+    /// Returns a cursor over the results of the last `execute`, binding each
+    /// result set with `binder`.
+    ///
+    /// Statements only return several results when they `CALL` a stored
+    /// procedure, which requires `CLIENT_PS_MULTI_RESULTS`.
+    pub fn results(&mut self, binder: ResultBinder) -> StatementResults<'_, 'conn> {
+        StatementResults {
+            stmt: self,
+            binder,
+            started: false,
+            done: false,
+        }
+    }
+}
+
+/// A cursor over the results of a prepared statement, advanced with
+/// `mysql_stmt_next_result`.
+///
+/// Each result borrows the cursor, and is freed with
+/// `mysql_stmt_free_result` when dropped. Dropping the cursor discards any
+/// remaining results.
+pub struct StatementResults<'stmt, 'conn: 'stmt> {
+    stmt: &'stmt mut Statement<'conn>,
+    binder: ResultBinder,
+    started: bool,
+    done: bool,
+}
+
+impl<'stmt, 'conn> StatementResults<'stmt, 'conn> {
+    /// Advances to the next result. Returns `None` once all results have
+    /// been read or after an error was returned.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<Result<StatementResult<'_, 'conn>>> {
+        if self.done {
+            return None;
+        }
+        if self.started {
+            match unsafe { mysql_stmt_next_result(self.stmt.as_ptr()) } {
+                0 => {}
+                -1 => {
+                    self.done = true;
+                    return None;
+                }
+                _ => {
+                    self.done = true;
+                    return Some(Err(self.stmt.last_error()));
+                }
+            }
+        }
+        self.started = true;
+        let conn = self.stmt.connection();
+        let out_params = conn.server_status() & SERVER_PS_OUT_PARAMS != 0;
+        let status = Status {
+            affected_rows: self.stmt.affected_rows(),
+            insert_id: self.stmt.insert_id(),
+            warnings: conn.warning_count(),
+        };
+        let item = match self.binder.bind(self.stmt) {
+            Ok(Some(rows)) if out_params => Ok(StatementResult::OutParams(rows)),
+            Ok(Some(rows)) => Ok(StatementResult::Rows(rows)),
+            Ok(None) => Ok(StatementResult::Status(status)),
+            Err(e) => Err(e),
+        };
+        if item.is_err() {
+            self.done = true;
+        }
+        Some(item)
+    }
+}
+
+impl<'stmt, 'conn> Drop for StatementResults<'stmt, 'conn> {
+    fn drop(&mut self) {
+        while self.next().is_some() {}
+    }
+}
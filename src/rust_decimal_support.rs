@@ -0,0 +1,30 @@
+//! Conversions between `DECIMAL` values and `rust_decimal::Decimal`.
+
+use rust_decimal::Decimal;
+
+use column::Column;
+use decimal::SqlDecimal;
+use decode::{self, DecodeError, FromSqlText};
+use enum_field_types;
+use params::{Param, ToSqlParam};
+
+/// Values with more than 28 significant digits fail with `OutOfRange` rather
+/// than being rounded.
+impl FromSqlText for Decimal {
+    fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+        let plain = decode::checked_decimal::<Decimal>(column, value)?;
+        Decimal::from_str_exact(&plain).map_err(|_| DecodeError::out_of_range::<Decimal>(column))
+    }
+}
+
+impl SqlDecimal for Decimal {
+    fn to_plain_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ToSqlParam for Decimal {
+    fn to_sql_param(&self) -> Param {
+        Param::bytes(enum_field_types::MYSQL_TYPE_NEWDECIMAL, self.to_string().into_bytes())
+    }
+}
@@ -0,0 +1,220 @@
+//! Parsing of server version strings across MySQL and its forks.
+
+use std::ffi::CStr;
+
+use connection::Connection;
+use {mysql_get_server_info, mysql_get_server_version};
+
+/// The server implementation behind a version string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Flavor {
+    MySQL,
+    MariaDB,
+    Percona,
+    TiDB,
+    Unknown,
+}
+
+/// A server version as reported by `mysql_get_server_info`.
+///
+/// For MariaDB and TiDB the version is that of the server itself, not the
+/// MySQL version they advertise for compatibility.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServerVersion {
+    pub flavor: Flavor,
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    /// Whatever follows the version number, such as `log` or
+    /// `MariaDB-1:10.11.6+maria~ubu2204-log`.
+    pub suffix: String,
+}
+
+/// The prefix MariaDB 10 and later put in front of their version, since
+/// replication clients of MySQL 5.5 reject major versions above 5.
+const MARIADB_RPL_HACK: &str = "5.5.5-";
+
+impl ServerVersion {
+    /// Parses strings such as `8.0.36-28 Percona`, `5.7.25-TiDB-v7.5.0` or
+    /// `5.5.5-10.11.6-MariaDB-log`. Returns `None` if the string does not
+    /// start with a version number.
+    pub fn parse(info: &str) -> Option<ServerVersion> {
+        let lower = info.to_ascii_lowercase();
+        if lower.contains("mariadb") {
+            let info = info.strip_prefix(MARIADB_RPL_HACK).unwrap_or(info);
+            return ServerVersion::parse_as(Flavor::MariaDB, info);
+        }
+        if let Some(idx) = lower.find("-tidb-v") {
+            return ServerVersion::parse_as(Flavor::TiDB, &info[idx + "-tidb-v".len()..]);
+        }
+        let flavor = if lower.contains("percona") {
+            Flavor::Percona
+        } else {
+            Flavor::MySQL
+        };
+        ServerVersion::parse_as(flavor, info)
+    }
+
+    /// Reads `major.minor[.patch]` from the start of `s`, keeping the rest as
+    /// the suffix.
+    fn parse_as(flavor: Flavor, s: &str) -> Option<ServerVersion> {
+        let mut rest = s;
+        let mut numbers = [0u32; 3];
+        for (idx, number) in numbers.iter_mut().enumerate() {
+            if idx > 0 {
+                match rest.strip_prefix('.') {
+                    Some(after) if after.starts_with(|c: char| c.is_ascii_digit()) => {
+                        rest = after;
+                    }
+                    _ if idx == 2 => break,
+                    _ => return None,
+                }
+            }
+            let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            *number = rest[..len].parse().ok()?;
+            rest = &rest[len..];
+        }
+        let suffix = rest.trim_start_matches(['-', ' ']).to_owned();
+        Some(ServerVersion {
+            flavor,
+            major: numbers[0],
+            minor: numbers[1],
+            patch: numbers[2],
+            suffix,
+        })
+    }
+
+    fn at_least(&self, major: u32, minor: u32, patch: u32) -> bool {
+        (self.major, self.minor, self.patch) >= (major, minor, patch)
+    }
+
+    /// Whether the server understands `COM_RESET_CONNECTION`, as used by
+    /// `mysql_reset_connection`: MySQL 5.7.3 and MariaDB 10.2.4 or later.
+    /// `false` where support is not known.
+    pub fn supports_reset_connection(&self) -> bool {
+        match self.flavor {
+            Flavor::MySQL | Flavor::Percona => self.at_least(5, 7, 3),
+            Flavor::MariaDB => self.at_least(10, 2, 4),
+            Flavor::TiDB | Flavor::Unknown => false,
+        }
+    }
+
+    /// Whether the server can report session state changes with
+    /// `CLIENT_SESSION_TRACK`: MySQL 5.7.4 and MariaDB 10.2.2 or later.
+    /// `false` where support is not known.
+    pub fn supports_session_track(&self) -> bool {
+        match self.flavor {
+            Flavor::MySQL | Flavor::Percona => self.at_least(5, 7, 4),
+            Flavor::MariaDB => self.at_least(10, 2, 2),
+            Flavor::TiDB | Flavor::Unknown => false,
+        }
+    }
+}
+
+impl Connection {
+    /// The version of the connected server.
+    ///
+    /// Falls back to `mysql_get_server_version`, with an unknown flavor, if
+    /// the version string cannot be parsed.
+    pub fn server_version(&self) -> ServerVersion {
+        let info = unsafe { mysql_get_server_info(self.as_ptr()) };
+        let info = if info.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(info) }.to_string_lossy().into_owned()
+        };
+        ServerVersion::parse(&info).unwrap_or_else(|| {
+            let id = unsafe { mysql_get_server_version(self.as_ptr()) } as u64;
+            ServerVersion {
+                flavor: Flavor::Unknown,
+                major: (id / 10_000) as u32,
+                minor: (id / 100 % 100) as u32,
+                patch: (id % 100) as u32,
+                suffix: info,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(flavor: Flavor, major: u32, minor: u32, patch: u32, suffix: &str) -> ServerVersion {
+        ServerVersion {
+            flavor,
+            major,
+            minor,
+            patch,
+            suffix: suffix.to_owned(),
+        }
+    }
+
+    #[test]
+    fn mariadb_with_replication_prefix() {
+        assert_eq!(ServerVersion::parse("5.5.5-10.11.6-MariaDB-1:10.11.6+maria~ubu2204-log"),
+                   Some(version(Flavor::MariaDB,
+                                10,
+                                11,
+                                6,
+                                "MariaDB-1:10.11.6+maria~ubu2204-log")));
+        assert_eq!(ServerVersion::parse("10.6.16-MariaDB"),
+                   Some(version(Flavor::MariaDB, 10, 6, 16, "MariaDB")));
+    }
+
+    #[test]
+    fn percona() {
+        assert_eq!(ServerVersion::parse("8.0.36-28 Percona"),
+                   Some(version(Flavor::Percona, 8, 0, 36, "28 Percona")));
+    }
+
+    #[test]
+    fn tidb_reports_its_own_version() {
+        assert_eq!(ServerVersion::parse("5.7.25-TiDB-v7.5.0"),
+                   Some(version(Flavor::TiDB, 7, 5, 0, "")));
+    }
+
+    #[test]
+    fn mysql() {
+        assert_eq!(ServerVersion::parse("8.0.36"),
+                   Some(version(Flavor::MySQL, 8, 0, 36, "")));
+        assert_eq!(ServerVersion::parse("5.7.44-log"),
+                   Some(version(Flavor::MySQL, 5, 7, 44, "log")));
+    }
+
+    #[test]
+    fn patch_is_optional() {
+        assert_eq!(ServerVersion::parse("5.7"), Some(version(Flavor::MySQL, 5, 7, 0, "")));
+        assert_eq!(ServerVersion::parse("8.4-lts"),
+                   Some(version(Flavor::MySQL, 8, 4, 0, "lts")));
+        assert_eq!(ServerVersion::parse("8.0."), Some(version(Flavor::MySQL, 8, 0, 0, ".")));
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for info in &["", "x", "8", "8.", "8.x", ".8.0", "v8.0.36", "99999999999.0.0"] {
+            assert_eq!(ServerVersion::parse(info), None, "{:?}", info);
+        }
+    }
+
+    #[test]
+    fn reset_connection_boundaries() {
+        assert!(!version(Flavor::MySQL, 5, 7, 2, "").supports_reset_connection());
+        assert!(version(Flavor::MySQL, 5, 7, 3, "").supports_reset_connection());
+        assert!(version(Flavor::Percona, 8, 0, 0, "").supports_reset_connection());
+        assert!(!version(Flavor::MariaDB, 10, 2, 3, "").supports_reset_connection());
+        assert!(version(Flavor::MariaDB, 10, 2, 4, "").supports_reset_connection());
+        assert!(!version(Flavor::TiDB, 7, 5, 0, "").supports_reset_connection());
+        assert!(!version(Flavor::Unknown, 9, 0, 0, "").supports_reset_connection());
+    }
+
+    #[test]
+    fn session_track_boundaries() {
+        assert!(!version(Flavor::MySQL, 5, 7, 3, "").supports_session_track());
+        assert!(version(Flavor::MySQL, 5, 7, 4, "").supports_session_track());
+        assert!(!version(Flavor::Percona, 5, 6, 99, "").supports_session_track());
+        assert!(!version(Flavor::MariaDB, 10, 2, 1, "").supports_session_track());
+        assert!(version(Flavor::MariaDB, 10, 2, 2, "").supports_session_track());
+        assert!(!version(Flavor::TiDB, 7, 5, 0, "").supports_session_track());
+    }
+}
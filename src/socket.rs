@@ -0,0 +1,64 @@
+//! Access to the socket of a connection, for external event loops and
+//! socket options such as TCP keepalive.
+
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+#[cfg(windows)]
+use std::os::windows::io::RawSocket;
+
+use connection::Connection;
+use MYSQL;
+
+#[cfg(unix)]
+type Socket = ::my_socket;
+#[cfg(windows)]
+type Socket = ::SOCKET;
+
+/// The socket `mysql` is connected through, or `None` if it is not
+/// connected.
+///
+/// MariaDB's client library is asked with `mysql_get_socket` when
+/// `AsyncConnection` drives its API; otherwise the descriptor is read from
+/// `net.fd`. The socket stays owned by the connection and must not be
+/// closed.
+#[cfg(unix)]
+pub fn socket_fd(mysql: &MYSQL) -> Option<RawFd> {
+    let fd = raw_socket(mysql);
+    if fd < 0 { None } else { Some(fd) }
+}
+
+/// The socket `mysql` is connected through, or `None` if it is not
+/// connected.
+///
+/// MariaDB's client library is asked with `mysql_get_socket` when
+/// `AsyncConnection` drives its API; otherwise the socket is read from
+/// `net.fd`. The socket stays owned by the connection and must not be
+/// closed.
+#[cfg(windows)]
+pub fn socket_fd(mysql: &MYSQL) -> Option<RawSocket> {
+    const INVALID_SOCKET: Socket = !0;
+    let socket = raw_socket(mysql);
+    if socket == INVALID_SOCKET { None } else { Some(socket as RawSocket) }
+}
+
+#[cfg(all(feature = "mariadb_nonblocking", not(feature = "mysql_nonblocking")))]
+fn raw_socket(mysql: &MYSQL) -> Socket {
+    unsafe { ::mariadb_nonblocking::mysql_get_socket(mysql) }
+}
+
+#[cfg(any(not(feature = "mariadb_nonblocking"), feature = "mysql_nonblocking"))]
+fn raw_socket(mysql: &MYSQL) -> Socket {
+    mysql.net.fd
+}
+
+impl Connection {
+    #[cfg(unix)]
+    pub fn socket_fd(&self) -> Option<RawFd> {
+        socket_fd(unsafe { &*self.as_ptr() })
+    }
+
+    #[cfg(windows)]
+    pub fn socket_fd(&self) -> Option<RawSocket> {
+        socket_fd(unsafe { &*self.as_ptr() })
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(feature = "tokio")]
+use std::mem;
+use std::os::raw::{c_char, c_ulong};
+use std::ptr::NonNull;
+
+use column::ResultMetadata;
+use connection::Connection;
+use error::{Error, MysqlError, Result};
+use params::Params;
+use {MYSQL_STMT, mysql_stmt_affected_rows, mysql_stmt_bind_param, mysql_stmt_close,
+     mysql_stmt_execute, mysql_stmt_field_count, mysql_stmt_init, mysql_stmt_insert_id,
+     mysql_stmt_param_count, mysql_stmt_prepare};
+
+/// A server-side prepared statement. The handle is closed on drop.
+///
+/// The statement borrows the connection it was prepared on, since the handle
+/// is invalidated when the connection is closed.
+pub struct Statement<'conn> {
+    raw: NonNull<MYSQL_STMT>,
+    params: Option<Params>,
+    conn: &'conn Connection,
+}
+
+impl Connection {
+    /// Prepares `sql` with `mysql_stmt_prepare`.
+    pub fn prepare(&self, sql: &str) -> Result<Statement<'_>> {
+        let raw = NonNull::new(unsafe { mysql_stmt_init(self.as_ptr()) })
+            .ok_or(Error::OutOfMemory)?;
+        let stmt = Statement {
+            raw,
+            params: None,
+            conn: self,
+        };
+        let rc = unsafe {
+            mysql_stmt_prepare(stmt.as_ptr(), sql.as_ptr() as *const c_char, sql.len() as c_ulong)
+        };
+        if rc == 0 {
+            Ok(stmt)
+        } else {
+            Err(stmt.last_error())
+        }
+    }
+}
+
+impl<'conn> Statement<'conn> {
+    pub fn as_ptr(&self) -> *mut MYSQL_STMT {
+        self.raw.as_ptr()
+    }
+
+    /// The connection the statement was prepared on.
+    pub fn connection(&self) -> &'conn Connection {
+        self.conn
+    }
+
+    /// The number of `?` placeholders in the statement.
+    pub fn param_count(&self) -> usize {
+        unsafe { mysql_stmt_param_count(self.as_ptr()) as usize }
+    }
+
+    /// The number of columns in the statement's result set.
+    pub fn field_count(&self) -> usize {
+        unsafe { mysql_stmt_field_count(self.as_ptr()) as usize }
+    }
+
+    /// Binds `params` to the statement's placeholders.
+    ///
+    /// The statement takes ownership of the parameters, since
+    /// `mysql_stmt_bind_param` only records pointers to their buffers which
+    /// are read again by every `execute`.
+    pub fn bind_params(&mut self, mut params: Params) -> Result<()> {
+        let expected = self.param_count();
+        if params.len() != expected {
+            return Err(Error::ParamCount {
+                expected,
+                actual: params.len(),
+            });
+        }
+        let binds = params.binds_mut();
+        if unsafe { mysql_stmt_bind_param(self.as_ptr(), binds) } != 0 {
+            return Err(self.last_error());
+        }
+        self.params = Some(params);
+        Ok(())
+    }
+
+    /// Executes the statement with the currently bound parameters.
+    pub fn execute(&mut self) -> Result<()> {
+        if unsafe { mysql_stmt_execute(self.as_ptr()) } == 0 {
+            Ok(())
+        } else {
+            Err(self.last_error())
+        }
+    }
+
+    pub fn affected_rows(&self) -> u64 {
+        unsafe { mysql_stmt_affected_rows(self.as_ptr()) as u64 }
+    }
+
+    pub fn insert_id(&self) -> u64 {
+        unsafe { mysql_stmt_insert_id(self.as_ptr()) as u64 }
+    }
+
+    /// The column metadata of the statement's result set, if it has one.
+    pub fn result_metadata(&self) -> Result<Option<ResultMetadata>> {
+        unsafe { ResultMetadata::from_statement(self.as_ptr()) }
+    }
+
+    /// Wraps a handle released by `into_raw`.
+    #[cfg(feature = "tokio")]
+    pub(crate) unsafe fn from_raw(raw: NonNull<MYSQL_STMT>, conn: &'conn Connection) -> Self {
+        Statement {
+            raw,
+            params: None,
+            conn,
+        }
+    }
+
+    /// Releases the handle without closing it. The bound parameters are
+    /// dropped, so parameters must be bound again before the next `execute`.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn into_raw(mut self) -> NonNull<MYSQL_STMT> {
+        self.params = None;
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+
+    pub(crate) fn last_error(&self) -> Error {
+        unsafe { MysqlError::from_statement(self.as_ptr()).into() }
+    }
+}
+
+impl<'conn> Drop for Statement<'conn> {
+    fn drop(&mut self) {
+        unsafe {
+            mysql_stmt_close(self.as_ptr());
+        }
+    }
+}
@@ -0,0 +1,406 @@
+//! Connections driven from async code by a dedicated worker thread.
+//!
+//! For client libraries without a nonblocking API, each `ThreadedConnection`
+//! owns a thread that makes the blocking calls, so the futures never block
+//! the executor. The futures do not depend on a particular runtime.
+//!
+//! Calls are queued and run in the order they were made. A call runs to
+//! completion on the worker even if its future is dropped.
+
+use std::future::{Future, poll_fn};
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::ptr::NonNull;
+use std::result;
+use std::sync::{Arc, mpsc};
+use std::task::{Context, Poll};
+use std::thread;
+
+use tokio::sync::{mpsc as bounded, oneshot};
+
+use binder::{ResultBinder, Value};
+use column::{Column, ColumnSet};
+use connection::{ConnectOptions, Connection, library_init};
+use decode::{DecodeError, FromSqlText};
+use error::{Error, Result};
+use params::Params;
+use result::BufferedResult;
+use results::Status;
+use statement::Statement;
+use {MYSQL_STMT, mysql_stmt_free_result, mysql_thread_end, mysql_thread_init};
+
+type Job = Box<dyn FnOnce(&mut Connection) + Send>;
+
+/// A connection whose blocking calls are made on its own thread.
+///
+/// The method names and signatures follow `AsyncConnection`, so that code
+/// can switch between the two. The worker thread exits once the connection
+/// and all of its statements have been dropped.
+pub struct ThreadedConnection {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl ThreadedConnection {
+    /// Spawns the worker thread, which calls `mysql_thread_init` and then
+    /// opens the connection.
+    pub fn connect(opts: &ConnectOptions) -> impl Future<Output = Result<ThreadedConnection>> {
+        let opts = opts.clone();
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let (connected, mut on_connect) = oneshot::channel();
+        // `mysql_thread_init` requires the library to be initialized.
+        library_init();
+        let spawned = thread::Builder::new().name("mysql-connection".into()).spawn(move || {
+            unsafe { mysql_thread_init() };
+            match Connection::connect(&opts) {
+                Ok(mut conn) => {
+                    let _ = connected.send(Ok(()));
+                    for job in queue {
+                        // A panicking job has already reported the panic to
+                        // its caller.
+                        let _ = panic::catch_unwind(AssertUnwindSafe(|| job(&mut conn)));
+                    }
+                }
+                Err(e) => {
+                    let _ = connected.send(Err(e));
+                }
+            }
+            unsafe { mysql_thread_end() };
+        });
+        let mut spawned = Some(spawned.map(drop));
+        let mut jobs = Some(jobs);
+        poll_fn(move |cx| {
+            if let Some(Err(e)) = spawned.take() {
+                return Poll::Ready(Err(e.into()));
+            }
+            Pin::new(&mut on_connect).poll(cx).map(|result| {
+                let jobs = jobs.take().expect("future polled after completion");
+                // The sender is only dropped unused if the worker panicked.
+                result.unwrap_or(Err(Error::WorkerGone)).map(|_| ThreadedConnection { jobs })
+            })
+        })
+    }
+
+    /// Sends `sql` and reads the server's reply. A result set must then be
+    /// read with `store_result`.
+    pub fn query(&mut self, sql: &str) -> impl Future<Output = Result<()>> {
+        let sql = sql.to_owned();
+        call(&self.jobs, move |conn| conn.query(&sql))
+    }
+
+    /// Reads the entire result set of the last query, or `None` if it did
+    /// not produce one.
+    pub fn store_result(&mut self) -> impl Future<Output = Result<Option<BufferedResult>>> {
+        call(&self.jobs, Connection::store_result)
+    }
+
+    /// Advances to the next result of a multi-statement query, returning
+    /// `false` if there are no more results.
+    pub fn next_result(&mut self) -> impl Future<Output = Result<bool>> {
+        call(&self.jobs, Connection::next_result)
+    }
+
+    /// Sends `sql` and streams its rows as they are read with
+    /// `mysql_use_result`, or returns `None` if it did not produce a result
+    /// set.
+    ///
+    /// At most `buffer` rows are read ahead of the receiver; after that the
+    /// worker stops reading from the network until rows are taken. Other
+    /// calls on the connection wait until the stream has been read to the end
+    /// or dropped.
+    pub fn query_stream(&mut self,
+                        sql: &str,
+                        buffer: usize)
+                        -> impl Future<Output = Result<Option<RowStream>>> {
+        let sql = sql.to_owned();
+        stream(&self.jobs, buffer, move |conn, rows| {
+            conn.query(&sql)?;
+            if let Some(mut result) = conn.use_result()? {
+                rows.send(ColumnSet::new(result.columns()), |send| {
+                    while let Some(row) = result.next_row()? {
+                        let values = (0..row.len()).map(|idx| row.get(idx).map(Vec::from));
+                        if !send(values.collect()) {
+                            break;
+                        }
+                    }
+                    Ok(())
+                });
+            }
+            Ok(())
+        })
+    }
+
+    /// Prepares `sql` with `mysql_stmt_prepare`.
+    pub fn prepare(&mut self, sql: &str) -> impl Future<Output = Result<ThreadedStatement>> {
+        let sql = sql.to_owned();
+        let jobs = self.jobs.clone();
+        call(&self.jobs, move |conn| {
+            conn.prepare(&sql).map(|stmt| {
+                ThreadedStatement {
+                    handle: StatementHandle(stmt.into_raw()),
+                    jobs,
+                }
+            })
+        })
+    }
+
+    /// Runs `f` with the connection on the worker thread, for calls that
+    /// have no method here. A panic in `f` is resumed when the future is
+    /// polled.
+    pub fn run<F, T>(&mut self, f: F) -> impl Future<Output = Result<T>>
+        where F: FnOnce(&mut Connection) -> T + Send + 'static,
+              T: Send + 'static
+    {
+        call(&self.jobs, move |conn| Ok(f(conn)))
+    }
+}
+
+/// A raw statement handle, only used by the worker thread.
+#[derive(Clone, Copy)]
+struct StatementHandle(NonNull<MYSQL_STMT>);
+
+unsafe impl Send for StatementHandle {}
+
+/// A prepared statement of a `ThreadedConnection`. The handle is closed on
+/// the worker thread when dropped.
+pub struct ThreadedStatement {
+    handle: StatementHandle,
+    jobs: mpsc::Sender<Job>,
+}
+
+impl ThreadedStatement {
+    /// Binds `params` and executes the statement, discarding any rows it
+    /// returns.
+    pub fn execute(&mut self, params: Params) -> impl Future<Output = Result<Status>> {
+        let handle = self.handle;
+        call(&self.jobs, move |conn| {
+            with_statement(conn, handle, |stmt| {
+                stmt.bind_params(params)?;
+                stmt.execute()?;
+                unsafe { mysql_stmt_free_result(stmt.as_ptr()) };
+                Ok(Status {
+                    affected_rows: stmt.affected_rows(),
+                    insert_id: stmt.insert_id(),
+                    warnings: stmt.connection().warning_count(),
+                })
+            })
+        })
+    }
+
+    /// Binds `params`, executes the statement and streams the rows it
+    /// returns, or returns `None` if it did not produce a result set.
+    ///
+    /// Values are converted to their text protocol form, so the rows decode
+    /// like those of `ThreadedConnection::query_stream`. `buffer` limits the
+    /// rows read ahead as in `query_stream`.
+    pub fn query(&mut self,
+                 params: Params,
+                 buffer: usize)
+                 -> impl Future<Output = Result<Option<RowStream>>> {
+        let handle = self.handle;
+        stream(&self.jobs, buffer, move |conn, rows| {
+            with_statement(conn, handle, |stmt| {
+                stmt.bind_params(params)?;
+                stmt.execute()?;
+                if let Some(mut result) = ResultBinder::new().bind(stmt)? {
+                    rows.send(ColumnSet::new(result.columns()), |send| {
+                        while let Some(row) = result.next_row()? {
+                            let values = (0..row.len()).map(|idx| text_value(row.get(idx)));
+                            if !send(values.collect()) {
+                                break;
+                            }
+                        }
+                        Ok(())
+                    });
+                }
+                Ok(())
+            })
+        })
+    }
+}
+
+impl Drop for ThreadedStatement {
+    fn drop(&mut self) {
+        let handle = self.handle;
+        let job: Job = Box::new(move |conn| drop(unsafe { Statement::from_raw(handle.0, conn) }));
+        let _ = self.jobs.send(job);
+    }
+}
+
+/// Lends the statement behind `handle` to `f`, keeping the handle open
+/// afterwards even if `f` panics.
+fn with_statement<F, T>(conn: &Connection, handle: StatementHandle, f: F) -> T
+    where F: FnOnce(&mut Statement) -> T
+{
+    struct Lent<'conn>(Option<Statement<'conn>>);
+
+    impl<'conn> Drop for Lent<'conn> {
+        fn drop(&mut self) {
+            if let Some(stmt) = self.0.take() {
+                stmt.into_raw();
+            }
+        }
+    }
+
+    let mut lent = Lent(Some(unsafe { Statement::from_raw(handle.0, conn) }));
+    f(lent.0.as_mut().expect("statement is lent"))
+}
+
+/// Converts a binary protocol value into the text the server would have
+/// sent for it.
+fn text_value(value: Value) -> Option<Vec<u8>> {
+    match value {
+        Value::Null => None,
+        Value::Int(n) => Some(n.to_string().into_bytes()),
+        Value::UInt(n) => Some(n.to_string().into_bytes()),
+        Value::Float(n) => Some(n.to_string().into_bytes()),
+        Value::Double(n) => Some(n.to_string().into_bytes()),
+        Value::Time(time) => Some(time.to_string().into_bytes()),
+        Value::Bytes(bytes) => Some(bytes.to_vec()),
+    }
+}
+
+/// Queues `f` and returns a future of its result, which fails with
+/// `Error::WorkerGone` if the worker thread is no longer running.
+fn call<F, T>(jobs: &mpsc::Sender<Job>, f: F) -> impl Future<Output = Result<T>>
+    where F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+          T: Send + 'static
+{
+    let (tx, mut rx) = oneshot::channel::<thread::Result<Result<T>>>();
+    let job: Job = Box::new(move |conn| {
+        let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(|| f(conn))));
+    });
+    // The worker only exits once every sender has been dropped.
+    let _ = jobs.send(job);
+    poll_fn(move |cx| {
+        match Pin::new(&mut rx).poll(cx) {
+            Poll::Ready(Ok(Ok(value))) => Poll::Ready(value),
+            Poll::Ready(Ok(Err(payload))) => panic::resume_unwind(payload),
+            Poll::Ready(Err(_)) => Poll::Ready(Err(Error::WorkerGone)),
+            Poll::Pending => Poll::Pending,
+        }
+    })
+}
+
+/// Hands a `RowStream` to the caller of a streaming call.
+struct RowSender {
+    buffer: usize,
+    stream: Option<oneshot::Sender<Result<Option<RowStream>>>>,
+}
+
+impl RowSender {
+    /// Returns a stream over `columns` to the caller, then feeds it the rows
+    /// produced by `produce`. `send` blocks while the stream's buffer is full,
+    /// and returns `false` once the stream has been dropped.
+    fn send<P>(&mut self, columns: ColumnSet, produce: P)
+        where P: FnOnce(&mut dyn FnMut(Vec<Option<Vec<u8>>>) -> bool) -> Result<()>
+    {
+        let stream = self.stream.take().expect("rows sent once");
+        let (tx, rx) = bounded::channel(self.buffer.max(1));
+        let columns = Arc::new(columns);
+        let _ = stream.send(Ok(Some(RowStream {
+            columns: columns.clone(),
+            rows: rx,
+        })));
+        let mut send = |values| {
+            tx.blocking_send(Ok(OwnedRow {
+                    values,
+                    columns: columns.clone(),
+                }))
+                .is_ok()
+        };
+        if let Err(e) = produce(&mut send) {
+            let _ = tx.blocking_send(Err(e));
+        }
+    }
+}
+
+/// Queues `f`, which starts a stream with `RowSender::send` if its
+/// statement produced a result set.
+fn stream<F>(jobs: &mpsc::Sender<Job>,
+             buffer: usize,
+             f: F)
+             -> impl Future<Output = Result<Option<RowStream>>>
+    where F: FnOnce(&mut Connection, &mut RowSender) -> Result<()> + Send + 'static
+{
+    let (tx, mut rx) = oneshot::channel();
+    let job: Job = Box::new(move |conn| {
+        let mut rows = RowSender {
+            buffer,
+            stream: Some(tx),
+        };
+        let result = f(conn, &mut rows);
+        if let Some(tx) = rows.stream.take() {
+            let _ = tx.send(result.map(|_| None));
+        }
+    });
+    let _ = jobs.send(job);
+    // The sender is only dropped unused if `f` panicked or the worker is gone.
+    poll_fn(move |cx| {
+        Pin::new(&mut rx).poll(cx).map(|result| result.unwrap_or(Err(Error::WorkerGone)))
+    })
+}
+
+/// Rows read by the worker thread, in the order the server sent them.
+pub struct RowStream {
+    columns: Arc<ColumnSet>,
+    rows: bounded::Receiver<Result<OwnedRow>>,
+}
+
+impl RowStream {
+    pub fn columns(&self) -> &[Column] {
+        self.columns.columns()
+    }
+
+    /// Receives the next row, or `None` after the last one. An error ends
+    /// the stream.
+    pub fn next_row(&mut self) -> impl Future<Output = Option<Result<OwnedRow>>> + '_ {
+        self.rows.recv()
+    }
+
+    pub fn poll_next_row(&mut self, cx: &mut Context) -> Poll<Option<Result<OwnedRow>>> {
+        self.rows.poll_recv(cx)
+    }
+}
+
+/// A row copied out of a result set, holding its values in text protocol
+/// form.
+pub struct OwnedRow {
+    values: Vec<Option<Vec<u8>>>,
+    columns: Arc<ColumnSet>,
+}
+
+impl OwnedRow {
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the bytes of column `idx`, or `None` if it is `NULL`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<&[u8]> {
+        self.values[idx].as_deref()
+    }
+
+    pub fn is_null(&self, idx: usize) -> bool {
+        self.values[idx].is_none()
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        self.columns.columns()
+    }
+
+    /// Decodes column `idx` into `T` according to the column's metadata.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn decode<T: FromSqlText>(&self, idx: usize) -> result::Result<T, DecodeError> {
+        T::from_sql_text(&self.columns()[idx], self.get(idx))
+    }
+}
@@ -0,0 +1,321 @@
+//! Conversions between `MYSQL_TIME` and `time` types.
+
+use std::convert::TryFrom;
+
+use time::{Date, Duration, Month, PrimitiveDateTime, Time};
+
+use column::Column;
+use datetime::{self, TimeConversionError};
+use decode::{self, DecodeError, FromSqlText};
+use params::{Param, ToSqlParam};
+use MYSQL_TIME;
+use enum_mysql_timestamp_type::*;
+
+fn date(t: &MYSQL_TIME) -> Result<Date, TimeConversionError> {
+    t.check_date()?;
+    let year = i32::try_from(t.year).map_err(|_| TimeConversionError::OutOfRange)?;
+    let month = u8::try_from(t.month)
+        .ok()
+        .and_then(|m| Month::try_from(m).ok())
+        .ok_or(TimeConversionError::Invalid)?;
+    let day = u8::try_from(t.day).map_err(|_| TimeConversionError::Invalid)?;
+    Date::from_calendar_date(year, month, day).map_err(|_| TimeConversionError::Invalid)
+}
+
+fn time_of_day(t: &MYSQL_TIME) -> Result<Time, TimeConversionError> {
+    let hour = u8::try_from(t.hour).map_err(|_| TimeConversionError::Invalid)?;
+    let minute = u8::try_from(t.minute).map_err(|_| TimeConversionError::Invalid)?;
+    let second = u8::try_from(t.second).map_err(|_| TimeConversionError::Invalid)?;
+    Time::from_hms_micro(hour, minute, second, t.microseconds())
+        .map_err(|_| TimeConversionError::Invalid)
+}
+
+/// Converts the date part of a `DATE` or `DATETIME` value.
+impl TryFrom<MYSQL_TIME> for Date {
+    type Error = TimeConversionError;
+
+    fn try_from(t: MYSQL_TIME) -> Result<Self, Self::Error> {
+        date(&t)
+    }
+}
+
+/// Converts a `DATETIME` value, or a `DATE` value at midnight.
+impl TryFrom<MYSQL_TIME> for PrimitiveDateTime {
+    type Error = TimeConversionError;
+
+    fn try_from(t: MYSQL_TIME) -> Result<Self, Self::Error> {
+        let time = match t.time_type {
+            MYSQL_TIMESTAMP_DATETIME => time_of_day(&t)?,
+            _ => Time::MIDNIGHT,
+        };
+        Ok(PrimitiveDateTime::new(date(&t)?, time))
+    }
+}
+
+/// Converts a non-negative `TIME` value below 24 hours, or the time of day of
+/// a `DATETIME` value. Use `Duration` for arbitrary `TIME` values.
+impl TryFrom<MYSQL_TIME> for Time {
+    type Error = TimeConversionError;
+
+    fn try_from(t: MYSQL_TIME) -> Result<Self, Self::Error> {
+        match t.time_type {
+            MYSQL_TIMESTAMP_TIME if t.is_negative() || t.hour >= 24 => {
+                Err(TimeConversionError::OutOfRange)
+            }
+            MYSQL_TIMESTAMP_TIME | MYSQL_TIMESTAMP_DATETIME => time_of_day(&t),
+            ty => Err(TimeConversionError::WrongType(ty)),
+        }
+    }
+}
+
+/// Converts a `TIME` value, including negative values and values above 24
+/// hours.
+impl TryFrom<MYSQL_TIME> for Duration {
+    type Error = TimeConversionError;
+
+    fn try_from(t: MYSQL_TIME) -> Result<Self, Self::Error> {
+        t.time_micros().map(Duration::microseconds)
+    }
+}
+
+/// Fails with `OutOfRange` for years before 0, which MySQL cannot store.
+impl TryFrom<Date> for MYSQL_TIME {
+    type Error = TimeConversionError;
+
+    fn try_from(date: Date) -> Result<Self, Self::Error> {
+        let year = u32::try_from(date.year()).map_err(|_| TimeConversionError::OutOfRange)?;
+        Ok(MYSQL_TIME::date(year, date.month() as u32, date.day() as u32))
+    }
+}
+
+/// Fails with `OutOfRange` for years before 0, which MySQL cannot store.
+impl TryFrom<PrimitiveDateTime> for MYSQL_TIME {
+    type Error = TimeConversionError;
+
+    fn try_from(dt: PrimitiveDateTime) -> Result<Self, Self::Error> {
+        let date = MYSQL_TIME::try_from(dt.date())?;
+        Ok(MYSQL_TIME::datetime(date.year,
+                                date.month,
+                                date.day,
+                                dt.hour() as u32,
+                                dt.minute() as u32,
+                                dt.second() as u32,
+                                dt.microsecond()))
+    }
+}
+
+impl From<Time> for MYSQL_TIME {
+    fn from(time: Time) -> Self {
+        MYSQL_TIME::time(false,
+                         time.hour() as u32,
+                         time.minute() as u32,
+                         time.second() as u32,
+                         time.microsecond())
+    }
+}
+
+/// Fails with `OutOfRange` outside of `-838:59:59.999999` to
+/// `838:59:59.999999`. Precision below a microsecond is truncated.
+impl TryFrom<Duration> for MYSQL_TIME {
+    type Error = TimeConversionError;
+
+    fn try_from(duration: Duration) -> Result<Self, Self::Error> {
+        let micros = i64::try_from(duration.whole_microseconds())
+            .map_err(|_| TimeConversionError::OutOfRange)?;
+        MYSQL_TIME::from_time_micros(micros)
+    }
+}
+
+macro_rules! time_sql_impls {
+    ($($ty:ty),*) => {$(
+        impl FromSqlText for $ty {
+            fn from_sql_text(column: &Column, value: Option<&[u8]>) -> Result<Self, DecodeError> {
+                decode::convert_time(column, value)
+            }
+        }
+    )*}
+}
+
+time_sql_impls!(Date, PrimitiveDateTime, Time, Duration);
+
+/// Dates before year 0 are sent as text, which the server rejects.
+impl ToSqlParam for Date {
+    fn to_sql_param(&self) -> Param {
+        match MYSQL_TIME::try_from(*self) {
+            Ok(t) => t.to_sql_param(),
+            Err(_) => datetime::text_param(self.to_string()),
+        }
+    }
+}
+
+/// Dates before year 0 are sent as text, which the server rejects.
+impl ToSqlParam for PrimitiveDateTime {
+    fn to_sql_param(&self) -> Param {
+        match MYSQL_TIME::try_from(*self) {
+            Ok(t) => t.to_sql_param(),
+            Err(_) => datetime::text_param(self.to_string()),
+        }
+    }
+}
+
+impl ToSqlParam for Time {
+    fn to_sql_param(&self) -> Param {
+        MYSQL_TIME::from(*self).to_sql_param()
+    }
+}
+
+/// Sent as a `TIME`. Durations beyond `838:59:59.999999` are sent as text,
+/// which the server rejects or clips to the range of `TIME`.
+impl ToSqlParam for Duration {
+    fn to_sql_param(&self) -> Param {
+        match MYSQL_TIME::try_from(*self) {
+            Ok(t) => t.to_sql_param(),
+            Err(_) => {
+                let text = datetime::duration_text(self.is_negative(),
+                                                   self.whole_seconds().unsigned_abs(),
+                                                   self.subsec_microseconds().unsigned_abs());
+                datetime::text_param(text)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binder::Value;
+    use enum_field_types::{self, MYSQL_TYPE_DATE, MYSQL_TYPE_DATETIME, MYSQL_TYPE_TIME};
+    use BINARY_CHARSET_NR;
+
+    fn ymd(year: i32, month: Month, day: u8) -> Date {
+        Date::from_calendar_date(year, month, day).unwrap()
+    }
+
+    fn hms_micro(hour: u8, minute: u8, second: u8, microsecond: u32) -> Time {
+        Time::from_hms_micro(hour, minute, second, microsecond).unwrap()
+    }
+
+    fn decode<T: FromSqlText>(column_type: enum_field_types, text: &str) -> Result<T, DecodeError> {
+        T::from_sql_text(&Column::for_test(column_type, 0, BINARY_CHARSET_NR),
+                         Some(text.as_bytes()))
+    }
+
+    fn sent_as_time(param: Param, buffer_type: enum_field_types) -> MYSQL_TIME {
+        assert_eq!(param.buffer_type(), buffer_type);
+        match param.value() {
+            Value::Time(t) => t,
+            other => panic!("unexpected value {:?}", other),
+        }
+    }
+
+    fn sent_as_text(param: Param) -> String {
+        assert_eq!(param.buffer_type(), enum_field_types::MYSQL_TYPE_STRING);
+        match param.value() {
+            Value::Bytes(bytes) => String::from_utf8(bytes.to_vec()).unwrap(),
+            other => panic!("unexpected value {:?}", other),
+        }
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(Date::try_from(MYSQL_TIME::date(2024, 2, 29)),
+                   Ok(ymd(2024, Month::February, 29)));
+        assert_eq!(Date::try_from(MYSQL_TIME::datetime(2024, 2, 29, 12, 0, 0, 0)),
+                   Ok(ymd(2024, Month::February, 29)));
+        assert_eq!(Date::try_from(MYSQL_TIME::date(2023, 2, 29)),
+                   Err(TimeConversionError::Invalid));
+        assert_eq!(Date::try_from(MYSQL_TIME::date(2024, 13, 1)),
+                   Err(TimeConversionError::Invalid));
+        assert_eq!(MYSQL_TIME::try_from(ymd(-1, Month::January, 1)),
+                   Err(TimeConversionError::OutOfRange));
+        assert_eq!(decode::<Date>(MYSQL_TYPE_DATE, "2024-01-31"),
+                   Ok(ymd(2024, Month::January, 31)));
+    }
+
+    #[test]
+    fn zero_dates() {
+        for t in &[MYSQL_TIME::date(0, 0, 0),
+                   MYSQL_TIME::date(2024, 0, 0),
+                   MYSQL_TIME::datetime(0, 0, 0, 0, 0, 0, 0)] {
+            assert_eq!(Date::try_from(*t), Err(TimeConversionError::ZeroDate));
+            assert_eq!(PrimitiveDateTime::try_from(*t), Err(TimeConversionError::ZeroDate));
+        }
+        assert!(decode::<PrimitiveDateTime>(MYSQL_TYPE_DATETIME, "0000-00-00 00:00:00").is_err());
+        assert!(decode::<Option<Date>>(MYSQL_TYPE_DATE, "0000-00-00").is_err());
+    }
+
+    #[test]
+    fn microseconds() {
+        let t = MYSQL_TIME::datetime(2024, 1, 31, 23, 59, 59, 1);
+        let dt = PrimitiveDateTime::new(ymd(2024, Month::January, 31), hms_micro(23, 59, 59, 1));
+        assert_eq!(PrimitiveDateTime::try_from(t), Ok(dt));
+        assert_eq!(MYSQL_TIME::try_from(dt), Ok(t));
+        assert_eq!(PrimitiveDateTime::try_from(MYSQL_TIME::date(2024, 1, 31)),
+                   Ok(PrimitiveDateTime::new(ymd(2024, Month::January, 31), Time::MIDNIGHT)));
+        // Nanoseconds are truncated.
+        let time = Time::from_hms_nano(0, 0, 0, 1_999).unwrap();
+        assert_eq!(MYSQL_TIME::from(time).second_part, 1);
+        assert_eq!(decode::<Time>(MYSQL_TYPE_TIME, "12:30:05.000250"),
+                   Ok(hms_micro(12, 30, 5, 250)));
+    }
+
+    #[test]
+    fn leap_seconds_are_rejected() {
+        // `time` has no leap seconds, so `:60` cannot come out of a conversion.
+        assert_eq!(Time::try_from(MYSQL_TIME::time(false, 23, 59, 60, 0)),
+                   Err(TimeConversionError::Invalid));
+        assert_eq!(PrimitiveDateTime::try_from(MYSQL_TIME::datetime(2016, 12, 31, 23, 59, 60, 0)),
+                   Err(TimeConversionError::Invalid));
+    }
+
+    #[test]
+    fn times_of_day() {
+        assert_eq!(Time::try_from(MYSQL_TIME::time(false, 23, 59, 59, 0)),
+                   Ok(hms_micro(23, 59, 59, 0)));
+        assert_eq!(Time::try_from(MYSQL_TIME::time(true, 1, 0, 0, 0)),
+                   Err(TimeConversionError::OutOfRange));
+        assert_eq!(Time::try_from(MYSQL_TIME::time(false, 24, 0, 0, 0)),
+                   Err(TimeConversionError::OutOfRange));
+        assert_eq!(Time::try_from(MYSQL_TIME::date(2024, 1, 31)),
+                   Err(TimeConversionError::WrongType(MYSQL_TIMESTAMP_DATE)));
+    }
+
+    #[test]
+    fn durations() {
+        let t = MYSQL_TIME::time(true, 838, 59, 59, 999_999);
+        let d = -(Duration::hours(838) + Duration::seconds(59 * 60 + 59) +
+                  Duration::microseconds(999_999));
+        assert_eq!(Duration::try_from(t), Ok(d));
+        assert_eq!(MYSQL_TIME::try_from(d), Ok(t));
+        assert_eq!(Duration::try_from(MYSQL_TIME::time(false, 100, 0, 0, 0)),
+                   Ok(Duration::hours(100)));
+        assert_eq!(decode::<Duration>(MYSQL_TYPE_TIME, "-00:00:01.5"),
+                   Ok(Duration::milliseconds(-1_500)));
+        assert_eq!(MYSQL_TIME::try_from(Duration::hours(839)),
+                   Err(TimeConversionError::OutOfRange));
+        assert_eq!(Duration::try_from(MYSQL_TIME::time(false, 839, 0, 0, 0)),
+                   Err(TimeConversionError::OutOfRange));
+    }
+
+    #[test]
+    fn params() {
+        let date = ymd(2024, Month::January, 31);
+        let dt = PrimitiveDateTime::new(date, hms_micro(12, 30, 5, 250));
+        assert_eq!(sent_as_time(date.to_sql_param(), MYSQL_TYPE_DATE),
+                   MYSQL_TIME::date(2024, 1, 31));
+        assert_eq!(sent_as_time(dt.to_sql_param(), MYSQL_TYPE_DATETIME),
+                   MYSQL_TIME::datetime(2024, 1, 31, 12, 30, 5, 250));
+        assert_eq!(sent_as_time(dt.time().to_sql_param(), MYSQL_TYPE_TIME),
+                   MYSQL_TIME::time(false, 12, 30, 5, 250));
+        assert_eq!(sent_as_time(Duration::seconds(-90).to_sql_param(), MYSQL_TYPE_TIME),
+                   MYSQL_TIME::time(true, 0, 1, 30, 0));
+    }
+
+    #[test]
+    fn values_out_of_range_are_sent_as_text() {
+        assert_eq!(sent_as_text(ymd(-1, Month::January, 1).to_sql_param()), "-0001-01-01");
+        assert_eq!(sent_as_text(Duration::hours(900).to_sql_param()), "900:00:00.000000");
+        let d = -(Duration::hours(1_000) + Duration::microseconds(1));
+        assert_eq!(sent_as_text(d.to_sql_param()), "-1000:00:00.000001");
+    }
+}
@@ -0,0 +1,273 @@
+//! Transactions that roll back unless explicitly committed.
+
+use std::ops::{Deref, DerefMut};
+
+use connection::Connection;
+use error::{Error, Result};
+use escape::quote_identifier;
+use {MYSQL, my_bool, mysql_commit, mysql_rollback};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IsolationLevel {
+    ReadUncommitted,
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    fn as_sql(&self) -> &'static str {
+        match *self {
+            IsolationLevel::ReadUncommitted => "READ UNCOMMITTED",
+            IsolationLevel::ReadCommitted => "READ COMMITTED",
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            IsolationLevel::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessMode {
+    ReadWrite,
+    ReadOnly,
+}
+
+/// Settings for a new `Transaction`. Unset values use the session defaults.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TransactionOptions {
+    isolation_level: Option<IsolationLevel>,
+    access_mode: Option<AccessMode>,
+}
+
+impl TransactionOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the isolation level with `SET TRANSACTION`, which only applies
+    /// to the transaction started next.
+    pub fn isolation_level(mut self, level: IsolationLevel) -> Self {
+        self.isolation_level = Some(level);
+        self
+    }
+
+    pub fn access_mode(mut self, mode: AccessMode) -> Self {
+        self.access_mode = Some(mode);
+        self
+    }
+
+    fn start_statement(&self) -> &'static str {
+        match self.access_mode {
+            Some(AccessMode::ReadWrite) => "START TRANSACTION READ WRITE",
+            Some(AccessMode::ReadOnly) => "START TRANSACTION READ ONLY",
+            None => "START TRANSACTION",
+        }
+    }
+}
+
+impl Connection {
+    /// Starts a transaction with the session's default settings.
+    pub fn transaction(&mut self) -> Result<Transaction<'_>> {
+        self.transaction_with(TransactionOptions::new())
+    }
+
+    /// Starts a transaction with `START TRANSACTION`.
+    ///
+    /// Fails with `Error::TransactionInProgress` if a transaction is already
+    /// open, since starting another one would implicitly commit it.
+    pub fn transaction_with(&mut self, opts: TransactionOptions) -> Result<Transaction<'_>> {
+        if self.in_transaction() {
+            return Err(Error::TransactionInProgress);
+        }
+        if let Some(level) = opts.isolation_level {
+            self.query(&format!("SET TRANSACTION ISOLATION LEVEL {}", level.as_sql()))?;
+        }
+        self.query(opts.start_statement())?;
+        Ok(Transaction {
+            conn: self,
+            done: false,
+        })
+    }
+}
+
+/// An open transaction, which is rolled back when dropped unless `commit`
+/// was called.
+///
+/// The transaction dereferences to its `Connection`, so statements are run
+/// through it as usual. Statements such as DDL implicitly commit the
+/// transaction on the server. `Transaction::query` reports this right after
+/// the statement with `Error::TransactionEnded`; statements run any other
+/// way, such as prepared statements, are only caught by `commit`,
+/// `rollback` and the savepoint methods, which then fail with the same
+/// error instead of acting on the following statements.
+pub struct Transaction<'conn> {
+    conn: &'conn mut Connection,
+    done: bool,
+}
+
+impl<'conn> Transaction<'conn> {
+    /// Whether the server still reports the transaction as open.
+    ///
+    /// This is `false` after a statement implicitly committed it, or after
+    /// the server rolled it back, as on a deadlock.
+    pub fn is_active(&self) -> bool {
+        self.conn.in_transaction()
+    }
+
+    /// Runs `sql` like `Connection::query`, then fails with
+    /// `Error::TransactionEnded` if the statement ended the transaction,
+    /// as DDL implicitly does. Its changes are then already committed.
+    pub fn query(&mut self, sql: &str) -> Result<()> {
+        self.conn.query(sql)?;
+        self.check_active()
+    }
+
+    pub fn commit(mut self) -> Result<()> {
+        self.finish(mysql_commit)
+    }
+
+    pub fn rollback(mut self) -> Result<()> {
+        self.finish(mysql_rollback)
+    }
+
+    /// Sets a savepoint with `SAVEPOINT`. An existing savepoint with the
+    /// same name is replaced.
+    pub fn savepoint(&mut self, name: &str) -> Result<()> {
+        self.savepoint_query("SAVEPOINT", name)
+    }
+
+    /// Undoes the changes made since the savepoint was set with
+    /// `ROLLBACK TO SAVEPOINT`. The savepoint is kept.
+    pub fn rollback_to(&mut self, name: &str) -> Result<()> {
+        self.savepoint_query("ROLLBACK TO SAVEPOINT", name)
+    }
+
+    /// Removes a savepoint with `RELEASE SAVEPOINT`, keeping its changes.
+    pub fn release(&mut self, name: &str) -> Result<()> {
+        self.savepoint_query("RELEASE SAVEPOINT", name)
+    }
+
+    fn savepoint_query(&mut self, command: &str, name: &str) -> Result<()> {
+        self.check_active()?;
+        let name = quote_identifier(self.conn, name)?;
+        self.conn.query(&format!("{} {}", command, name))
+    }
+
+    fn check_active(&self) -> Result<()> {
+        if self.is_active() {
+            Ok(())
+        } else {
+            Err(Error::TransactionEnded)
+        }
+    }
+
+    fn finish(&mut self, end: unsafe extern "C" fn(*mut MYSQL) -> my_bool) -> Result<()> {
+        self.done = true;
+        self.check_active()?;
+        if unsafe { end(self.conn.as_ptr()) } == 0 {
+            Ok(())
+        } else {
+            Err(self.conn.last_error())
+        }
+    }
+}
+
+impl<'conn> Deref for Transaction<'conn> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+    }
+}
+
+impl<'conn> DerefMut for Transaction<'conn> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn
+    }
+}
+
+impl<'conn> Drop for Transaction<'conn> {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.finish(mysql_rollback);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use connection::ConnectOptions;
+
+    /// Connects with the client library's defaults and option files. Tests
+    /// that need a server are ignored; run them with
+    /// `cargo test -- --ignored`.
+    fn connect() -> Connection {
+        Connection::connect(&ConnectOptions::new()).expect("cannot reach a server")
+    }
+
+    fn assert_ended(result: Result<()>) {
+        match result {
+            Err(Error::TransactionEnded) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn isolation_levels() {
+        assert_eq!(IsolationLevel::ReadUncommitted.as_sql(), "READ UNCOMMITTED");
+        assert_eq!(IsolationLevel::ReadCommitted.as_sql(), "READ COMMITTED");
+        assert_eq!(IsolationLevel::RepeatableRead.as_sql(), "REPEATABLE READ");
+        assert_eq!(IsolationLevel::Serializable.as_sql(), "SERIALIZABLE");
+    }
+
+    #[test]
+    fn start_statements() {
+        let opts = TransactionOptions::new();
+        assert_eq!(opts.start_statement(), "START TRANSACTION");
+        assert_eq!(opts.access_mode(AccessMode::ReadOnly).start_statement(),
+                   "START TRANSACTION READ ONLY");
+        assert_eq!(opts.access_mode(AccessMode::ReadWrite).start_statement(),
+                   "START TRANSACTION READ WRITE");
+    }
+
+    #[test]
+    #[ignore]
+    fn nested_transactions_are_rejected() {
+        let mut conn = connect();
+        conn.query("START TRANSACTION").unwrap();
+        assert!(conn.in_transaction());
+        match conn.transaction().map(|_| ()) {
+            Err(Error::TransactionInProgress) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn implicit_commits_are_reported() {
+        let mut conn = connect();
+        let mut tx = conn.transaction().unwrap();
+        assert!(tx.is_active());
+        tx.query("SELECT 1").unwrap();
+        tx.store_result().unwrap();
+        // Turning autocommit on commits the open transaction.
+        assert_ended(tx.query("SET autocommit = 1"));
+        assert_ended(tx.savepoint("a"));
+        assert_ended(tx.commit());
+        assert!(!conn.in_transaction());
+    }
+
+    #[test]
+    #[ignore]
+    fn dropping_rolls_back() {
+        let mut conn = connect();
+        {
+            let mut tx = conn.transaction().unwrap();
+            tx.savepoint("a`b").unwrap();
+            tx.rollback_to("a`b").unwrap();
+            tx.release("a`b").unwrap();
+        }
+        assert!(!conn.in_transaction());
+    }
+}